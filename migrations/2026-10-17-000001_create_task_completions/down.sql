@@ -0,0 +1,2 @@
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
@@ -0,0 +1,11 @@
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at VARCHAR NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+
+-- The only history we have so far is the last date of each task.
+INSERT INTO task_completions(task_id, completed_at) SELECT id, updated_at FROM tasks;
@@ -0,0 +1,2 @@
+ALTER TABLE tasks DROP COLUMN interval_unit;
+ALTER TABLE tasks DROP COLUMN interval_count;
@@ -0,0 +1,2 @@
+ALTER TABLE tasks ADD COLUMN interval_count INTEGER;
+ALTER TABLE tasks ADD COLUMN interval_unit VARCHAR;
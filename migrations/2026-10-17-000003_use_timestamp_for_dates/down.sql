@@ -0,0 +1,33 @@
+-- Keep completions aside so that dropping `tasks` never cascades to them.
+CREATE TEMP TABLE completions_backup AS
+    SELECT id, task_id, completed_at, note FROM task_completions;
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
+
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at VARCHAR NOT NULL DEFAULT '2020-01-01',
+    label_id INTEGER,
+    interval_count INTEGER,
+    interval_unit VARCHAR,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+INSERT INTO tasks_new(id, name, description, updated_at, label_id, interval_count, interval_unit)
+    SELECT id, name, description, updated_at, label_id, interval_count, interval_unit
+    FROM tasks;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at VARCHAR NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+INSERT INTO task_completions(id, task_id, completed_at, note)
+    SELECT id, task_id, completed_at, note FROM completions_backup;
+DROP TABLE completions_backup;
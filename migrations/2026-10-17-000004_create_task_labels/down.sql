@@ -0,0 +1,37 @@
+-- A task keeps only one of its labels.
+CREATE TEMP TABLE completions_backup AS SELECT * FROM task_completions;
+CREATE TEMP TABLE task_labels_backup AS
+    SELECT task_id, MIN(label_id) AS label_id FROM task_labels GROUP BY task_id;
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
+DROP INDEX task_labels_label_id;
+DROP TABLE task_labels;
+
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    label_id INTEGER,
+    interval_count INTEGER,
+    interval_unit VARCHAR,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+INSERT INTO tasks_new(id, name, description, updated_at, label_id, interval_count, interval_unit)
+    SELECT tasks.id, name, description, updated_at, task_labels_backup.label_id,
+        interval_count, interval_unit
+    FROM tasks LEFT JOIN task_labels_backup ON task_labels_backup.task_id = tasks.id;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+DROP TABLE task_labels_backup;
+
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at TIMESTAMP NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+INSERT INTO task_completions SELECT * FROM completions_backup;
+DROP TABLE completions_backup;
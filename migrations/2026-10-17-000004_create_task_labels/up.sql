@@ -0,0 +1,54 @@
+CREATE TABLE task_labels (
+    task_id INTEGER NOT NULL,
+    label_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, label_id),
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_labels_label_id ON task_labels (label_id);
+INSERT INTO task_labels(task_id, label_id)
+    SELECT tasks.id, tasks.label_id FROM tasks JOIN labels ON labels.id = tasks.label_id;
+
+-- `label_id` is in a foreign key, so rebuild `tasks` without it.
+-- Keep tables referring to `tasks` aside so that dropping it never cascades.
+CREATE TEMP TABLE completions_backup AS SELECT * FROM task_completions;
+CREATE TEMP TABLE task_labels_backup AS SELECT * FROM task_labels;
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
+DROP INDEX task_labels_label_id;
+DROP TABLE task_labels;
+
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    interval_count INTEGER,
+    interval_unit VARCHAR
+);
+INSERT INTO tasks_new(id, name, description, updated_at, interval_count, interval_unit)
+    SELECT id, name, description, updated_at, interval_count, interval_unit FROM tasks;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at TIMESTAMP NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+INSERT INTO task_completions SELECT * FROM completions_backup;
+DROP TABLE completions_backup;
+
+CREATE TABLE task_labels (
+    task_id INTEGER NOT NULL,
+    label_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, label_id),
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_labels_label_id ON task_labels (label_id);
+INSERT INTO task_labels SELECT * FROM task_labels_backup;
+DROP TABLE task_labels_backup;
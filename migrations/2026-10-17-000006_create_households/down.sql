@@ -0,0 +1,57 @@
+-- All households are merged into one list again.
+CREATE TEMP TABLE completions_backup AS SELECT * FROM task_completions;
+CREATE TEMP TABLE task_labels_backup AS SELECT * FROM task_labels;
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
+DROP INDEX task_labels_label_id;
+DROP TABLE task_labels;
+
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    interval_count INTEGER,
+    interval_unit VARCHAR
+);
+INSERT INTO tasks_new(id, name, description, updated_at, interval_count, interval_unit)
+    SELECT id, name, description, updated_at, interval_count, interval_unit FROM tasks;
+DROP INDEX tasks_household_id;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE TABLE labels_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR,
+    color_hex VARCHAR
+);
+INSERT INTO labels_new(id, name, color_hex) SELECT id, name, color_hex FROM labels;
+DROP INDEX labels_household_id;
+DROP TABLE labels;
+ALTER TABLE labels_new RENAME TO labels;
+
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at TIMESTAMP NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+INSERT INTO task_completions SELECT * FROM completions_backup;
+DROP TABLE completions_backup;
+
+CREATE TABLE task_labels (
+    task_id INTEGER NOT NULL,
+    label_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, label_id),
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_labels_label_id ON task_labels (label_id);
+INSERT INTO task_labels SELECT * FROM task_labels_backup;
+DROP TABLE task_labels_backup;
+
+DROP INDEX household_members_user_id;
+DROP TABLE household_members;
+DROP TABLE households;
@@ -0,0 +1,76 @@
+CREATE TABLE households (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL
+);
+CREATE TABLE household_members (
+    household_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    PRIMARY KEY (household_id, user_id),
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX household_members_user_id ON household_members (user_id);
+
+-- Everything so far belongs to one household shared by all existing users.
+INSERT INTO households(id, name) VALUES (1, 'Home');
+INSERT INTO household_members(household_id, user_id) SELECT 1, id FROM users;
+
+-- Rebuild `tasks` and `labels` with `household_id` in a foreign key.
+-- Keep tables referring to them aside so that dropping them never cascades.
+CREATE TEMP TABLE completions_backup AS SELECT * FROM task_completions;
+CREATE TEMP TABLE task_labels_backup AS SELECT * FROM task_labels;
+DROP INDEX task_completions_task_id;
+DROP TABLE task_completions;
+DROP INDEX task_labels_label_id;
+DROP TABLE task_labels;
+
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    interval_count INTEGER,
+    interval_unit VARCHAR,
+    household_id INTEGER NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO tasks_new(id, name, description, updated_at, interval_count, interval_unit, household_id)
+    SELECT id, name, description, updated_at, interval_count, interval_unit, 1 FROM tasks;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+CREATE INDEX tasks_household_id ON tasks (household_id);
+
+CREATE TABLE labels_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR,
+    color_hex VARCHAR,
+    household_id INTEGER NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO labels_new(id, name, color_hex, household_id)
+    SELECT id, name, color_hex, 1 FROM labels;
+DROP TABLE labels;
+ALTER TABLE labels_new RENAME TO labels;
+CREATE INDEX labels_household_id ON labels (household_id);
+
+CREATE TABLE task_completions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    completed_at TIMESTAMP NOT NULL,
+    note VARCHAR,
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+INSERT INTO task_completions SELECT * FROM completions_backup;
+DROP TABLE completions_backup;
+
+CREATE TABLE task_labels (
+    task_id INTEGER NOT NULL,
+    label_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, label_id),
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX task_labels_label_id ON task_labels (label_id);
+INSERT INTO task_labels SELECT * FROM task_labels_backup;
+DROP TABLE task_labels_backup;
@@ -0,0 +1,2 @@
+DROP INDEX households_calendar_token;
+ALTER TABLE households DROP COLUMN calendar_token;
@@ -0,0 +1,3 @@
+-- Secret for calendar feeds, which calendar clients fetch without logging in.
+ALTER TABLE households ADD COLUMN calendar_token VARCHAR;
+CREATE UNIQUE INDEX households_calendar_token ON households (calendar_token);
@@ -0,0 +1,23 @@
+CREATE TABLE webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    household_id INTEGER NOT NULL,
+    url VARCHAR NOT NULL,
+    secret VARCHAR NOT NULL,
+    -- Comma separated event names like 'task.completed,task.deleted'
+    events VARCHAR NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX webhooks_household_id ON webhooks (household_id);
+
+-- One row per attempt to deliver an event.
+CREATE TABLE webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id INTEGER NOT NULL,
+    event VARCHAR NOT NULL,
+    attempt INTEGER NOT NULL,
+    status_code INTEGER,
+    error VARCHAR,
+    attempted_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
@@ -0,0 +1,2 @@
+DROP TABLE task_reminders;
+ALTER TABLE users DROP COLUMN email;
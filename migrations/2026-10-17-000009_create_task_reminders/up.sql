@@ -0,0 +1,11 @@
+-- Address reminders are sent to. Users without one get none.
+ALTER TABLE users ADD COLUMN email VARCHAR;
+
+-- Reminders already sent. A task is reminded of once per last updated date.
+CREATE TABLE task_reminders (
+    task_id INTEGER NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    sent_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (task_id, updated_at),
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,15 @@
+-- Full-text index of task names and descriptions for search. It reads the
+-- text from tasks, and the triggers keep it in sync.
+CREATE VIRTUAL TABLE tasks_fts USING fts5(name, description, content='tasks', content_rowid='id');
+INSERT INTO tasks_fts(rowid, name, description) SELECT id, name, description FROM tasks;
+
+CREATE TRIGGER tasks_fts_after_insert AFTER INSERT ON tasks BEGIN
+    INSERT INTO tasks_fts(rowid, name, description) VALUES (new.id, new.name, new.description);
+END;
+CREATE TRIGGER tasks_fts_after_delete AFTER DELETE ON tasks BEGIN
+    INSERT INTO tasks_fts(tasks_fts, rowid, name, description) VALUES ('delete', old.id, old.name, old.description);
+END;
+CREATE TRIGGER tasks_fts_after_update AFTER UPDATE OF name, description ON tasks BEGIN
+    INSERT INTO tasks_fts(tasks_fts, rowid, name, description) VALUES ('delete', old.id, old.name, old.description);
+    INSERT INTO tasks_fts(rowid, name, description) VALUES (new.id, new.name, new.description);
+END;
@@ -0,0 +1,2 @@
+ALTER TABLE tasks DROP COLUMN archived_at;
+ALTER TABLE tasks DROP COLUMN deleted_at;
@@ -0,0 +1,4 @@
+-- Tasks in the trash, which can be restored until they are purged.
+ALTER TABLE tasks ADD COLUMN deleted_at TIMESTAMP;
+-- Tasks hidden from the index page, like seasonal ones.
+ALTER TABLE tasks ADD COLUMN archived_at TIMESTAMP;
@@ -0,0 +1,9 @@
+-- Changes which can be undone for a while. `action` is JSON of what undoes
+-- the change.
+CREATE TABLE undo_actions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    household_id INTEGER NOT NULL,
+    action VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Every change of tasks and labels. `before` and `after` are JSON of the
+-- entity, missing when it was created or deleted.
+CREATE TABLE audit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    household_id INTEGER NOT NULL,
+    user_id INTEGER,
+    event VARCHAR NOT NULL,
+    entity_id INTEGER NOT NULL,
+    before VARCHAR,
+    after VARCHAR,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+CREATE INDEX audit_events_household_id ON audit_events (household_id);
@@ -0,0 +1,11 @@
+DROP TABLE undo_actions;
+DROP TABLE task_reminders;
+DROP TABLE webhook_deliveries;
+DROP TABLE webhooks;
+DROP TABLE task_labels;
+DROP TABLE task_completions;
+DROP TABLE labels;
+DROP TABLE tasks;
+DROP TABLE household_members;
+DROP TABLE users;
+DROP TABLE households;
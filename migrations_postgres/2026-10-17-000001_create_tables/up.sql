@@ -0,0 +1,106 @@
+-- The schema of all SQLite migrations up to 2026-10-17-000012 at once.
+-- Names are compared byte by byte like in SQLite.
+
+CREATE TABLE households (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    -- Secret for calendar feeds, which calendar clients fetch without logging in.
+    calendar_token VARCHAR UNIQUE
+);
+
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    password_hash VARCHAR NOT NULL,
+    -- Address reminders are sent to. Users without one get none.
+    email VARCHAR
+);
+
+CREATE TABLE household_members (
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (household_id, user_id)
+);
+CREATE INDEX household_members_user_id ON household_members (user_id);
+
+CREATE TABLE tasks (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR COLLATE "C" NOT NULL,
+    description VARCHAR NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    interval_count INTEGER,
+    interval_unit VARCHAR,
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    -- Tasks in the trash, which can be restored until they are purged.
+    deleted_at TIMESTAMP,
+    -- Tasks hidden from the index page, like seasonal ones.
+    archived_at TIMESTAMP,
+    -- Words of the name and description for search, names ranking higher.
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') || setweight(to_tsvector('simple', description), 'B')
+    ) STORED
+);
+CREATE INDEX tasks_household_id ON tasks (household_id);
+CREATE INDEX tasks_search_vector ON tasks USING GIN (search_vector);
+
+CREATE TABLE labels (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR COLLATE "C" NOT NULL,
+    color_hex VARCHAR NOT NULL,
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX labels_household_id ON labels (household_id);
+
+CREATE TABLE task_completions (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    completed_at TIMESTAMP NOT NULL,
+    note VARCHAR
+);
+CREATE INDEX task_completions_task_id ON task_completions (task_id);
+
+CREATE TABLE task_labels (
+    task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    label_id INTEGER NOT NULL REFERENCES labels (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (task_id, label_id)
+);
+CREATE INDEX task_labels_label_id ON task_labels (label_id);
+
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    url VARCHAR NOT NULL,
+    secret VARCHAR NOT NULL,
+    -- Comma separated event names like 'task.completed,task.deleted'
+    events VARCHAR NOT NULL
+);
+CREATE INDEX webhooks_household_id ON webhooks (household_id);
+
+-- One row per attempt to deliver an event.
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    event VARCHAR NOT NULL,
+    attempt INTEGER NOT NULL,
+    status_code INTEGER,
+    error VARCHAR,
+    attempted_at TIMESTAMP NOT NULL
+);
+CREATE INDEX webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
+
+-- Reminders already sent. A task is reminded of once per last updated date.
+CREATE TABLE task_reminders (
+    task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    updated_at TIMESTAMP NOT NULL,
+    sent_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (task_id, updated_at)
+);
+
+-- Changes which can be undone for a while. `action` is JSON of what undoes
+-- the change.
+CREATE TABLE undo_actions (
+    id SERIAL PRIMARY KEY,
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    action VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
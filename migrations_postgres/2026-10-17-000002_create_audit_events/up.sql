@@ -0,0 +1,14 @@
+-- Every change of tasks and labels. `before` and `after` are JSON of the
+-- entity, missing when it was created or deleted.
+CREATE TABLE audit_events (
+    id SERIAL PRIMARY KEY,
+    household_id INTEGER NOT NULL REFERENCES households (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    user_id INTEGER REFERENCES users (id) ON DELETE SET NULL ON UPDATE CASCADE,
+    event VARCHAR NOT NULL,
+    entity_id INTEGER NOT NULL,
+    before VARCHAR,
+    after VARCHAR,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX audit_events_household_id ON audit_events (household_id);
@@ -0,0 +1,13 @@
+-- Events waiting to be sent to a webhook, kept until they are delivered or
+-- out of attempts so that they survive restarts.
+CREATE TABLE webhook_queue (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    event VARCHAR NOT NULL,
+    body VARCHAR NOT NULL,
+    -- Attempts made so far
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX webhook_queue_next_attempt_at ON webhook_queue (next_attempt_at);
@@ -0,0 +1,18 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use last_date::cli::{self, Args};
+
+#[rocket::main]
+async fn main() -> ExitCode {
+    match cli::main(Args::parse()).await {
+        Ok(out) => {
+            print!("{out}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! iCalendar (RFC 5545) feeds of tasks.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::label::Label;
+use crate::models::task::TaskSummary;
+
+/// Lines longer than this many octets are folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Renders the tasks as a calendar named `name`.
+///
+/// Each task is a `VTODO` which starts on its last date and is due on its
+/// next due date, if it has an interval. Its labels are `CATEGORIES`.
+pub fn render(name: &str, tasks: &[TaskSummary], labels: &[Label], now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//last-date//last-date//EN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+    ];
+    for t in tasks {
+        let Some(id) = t.task.id else {
+            continue;
+        };
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:task-{id}@last-date"));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("SUMMARY:{}", escape(&t.task.name)));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            t.task.updated_at.format("%Y%m%d")
+        ));
+        if let Some(due) = t.next_due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        if !t.task.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&t.task.description)));
+        }
+        let categories: Vec<String> = labels
+            .iter()
+            .filter(|l| l.id.is_some_and(|id| t.label_ids.contains(&id)))
+            .map(|l| escape(&l.name))
+            .collect();
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|l| fold(l)).collect()
+}
+
+/// Escapes a TEXT value.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line and terminates it with CRLF.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut octets = 0;
+    for ch in line.chars() {
+        if octets + ch.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
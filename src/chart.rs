@@ -0,0 +1,110 @@
+//! Inline SVG charts of completions.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Weeks shown by the heatmap, including the one of today.
+const HEATMAP_WEEKS: i64 = 53;
+/// Size of a day in the heatmap, with the gap after it.
+const CELL: i64 = 12;
+const CELL_GAP: i64 = 2;
+/// Colors for days done 0, 1, 2, 3 and 4 or more times.
+const HEATMAP_COLORS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+const BAR_WIDTH: i64 = 14;
+const BAR_GAP: i64 = 4;
+const CHART_HEIGHT: i64 = 120;
+/// Room for the labels of the axes.
+const AXIS_MARGIN: i64 = 30;
+
+/// Renders a calendar heatmap of the dates, one column a week up to the
+/// one of `today` and one row a weekday starting on Sunday.
+pub fn heatmap(dates: &[NaiveDate], today: NaiveDate) -> String {
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for &date in dates {
+        *counts.entry(date).or_default() += 1;
+    }
+    let weekday = i64::from(today.weekday().num_days_from_sunday());
+    let start = today - Duration::days(weekday + 7 * (HEATMAP_WEEKS - 1));
+    let width = HEATMAP_WEEKS * (CELL + CELL_GAP);
+    let height = 7 * (CELL + CELL_GAP) + AXIS_MARGIN / 2;
+
+    let mut svg = format!(
+        r#"<svg class="chart heatmap" xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" role="img" aria-label="Completions by day">"#
+    );
+    let mut day = start;
+    let mut last_month = None;
+    while day <= today {
+        let week = (day - start).num_days() / 7;
+        let x = week * (CELL + CELL_GAP);
+        if last_month != Some(day.month()) && day.weekday().num_days_from_sunday() == 0 {
+            last_month = Some(day.month());
+            let _ = write!(
+                svg,
+                r##"<text x="{x}" y="{y}" font-size="9" fill="#767676">{month}</text>"##,
+                y = height - 2,
+                month = day.format("%b"),
+            );
+        }
+        let count = counts.get(&day).copied().unwrap_or_default();
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" rx="2" fill="{color}"><title>{date}: {count} done</title></rect>"#,
+            y = i64::from(day.weekday().num_days_from_sunday()) * (CELL + CELL_GAP),
+            color = HEATMAP_COLORS[count.min(HEATMAP_COLORS.len() - 1)],
+            date = day.format("%Y-%m-%d"),
+        );
+        day += Duration::days(1);
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Renders a bar chart of the days between consecutive dates, the oldest
+/// one first. Returns `None` with fewer than two dates.
+///
+/// `dates` must be sorted, the oldest one first.
+pub fn intervals(dates: &[NaiveDate]) -> Option<String> {
+    if dates.len() < 2 {
+        return None;
+    }
+    let bars: Vec<(NaiveDate, i64)> = dates
+        .windows(2)
+        .map(|w| (w[1], (w[1] - w[0]).num_days()))
+        .collect();
+    let max = bars.iter().map(|&(_, days)| days).max().unwrap_or(0).max(1);
+    let plot_width = i64::try_from(bars.len()).unwrap_or(i64::MAX) * (BAR_WIDTH + BAR_GAP);
+    let width = AXIS_MARGIN + plot_width;
+    let height = CHART_HEIGHT + AXIS_MARGIN / 2;
+
+    let mut svg = format!(
+        r#"<svg class="chart intervals" xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" role="img" aria-label="Days between completions">"#
+    );
+    let _ = write!(
+        svg,
+        r##"<text x="0" y="10" font-size="9" fill="#767676">{max}d</text><text x="0" y="{CHART_HEIGHT}" font-size="9" fill="#767676">0d</text><line x1="{AXIS_MARGIN}" y1="{CHART_HEIGHT}" x2="{width}" y2="{CHART_HEIGHT}" stroke="#dbdbdb"/>"##
+    );
+    for (i, &(date, days)) in bars.iter().enumerate() {
+        let x = AXIS_MARGIN + i64::try_from(i).unwrap_or(i64::MAX) * (BAR_WIDTH + BAR_GAP);
+        let bar_height = (days * CHART_HEIGHT / max).max(1);
+        let _ = write!(
+            svg,
+            r##"<rect x="{x}" y="{y}" width="{BAR_WIDTH}" height="{bar_height}" fill="#3e8ed0"><title>{date}: {days} day(s)</title></rect>"##,
+            y = CHART_HEIGHT - bar_height,
+            date = date.format("%Y-%m-%d"),
+        );
+    }
+    if let (Some(&(first, _)), Some(&(last, _))) = (bars.first(), bars.last()) {
+        let _ = write!(
+            svg,
+            r##"<text x="{AXIS_MARGIN}" y="{y}" font-size="9" fill="#767676">{first}</text><text x="{width}" y="{y}" font-size="9" fill="#767676" text-anchor="end">{last}</text>"##,
+            y = height - 2,
+            first = first.format("%Y-%m-%d"),
+            last = last.format("%Y-%m-%d"),
+        );
+    }
+    svg.push_str("</svg>");
+    Some(svg)
+}
@@ -0,0 +1,419 @@
+//! `last-date-cli`, which manages tasks and labels of a household from the
+//! terminal with the database of `Rocket.toml`.
+
+use std::collections::HashMap;
+
+use clap::{Parser, Subcommand};
+use rocket::config::LogLevel;
+use rocket::fairing::AdHoc;
+use serde::Serialize;
+
+use crate::models::household::Household;
+use crate::models::label::{DEFAULT_COLOR, Label, LabelForm};
+use crate::models::schedule::DueStatus;
+use crate::models::task::{Task, TaskName, TaskQuery, TaskSummary};
+use crate::models::user::User;
+use crate::models::validation::FieldError;
+use crate::tui;
+use crate::{DbConn, run_db_migrations};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "last-date-cli",
+    about = "Manage last-date tasks from the terminal"
+)]
+pub struct Args {
+    /// Id of the household to manage. The oldest household by default.
+    #[arg(long, global = true)]
+    pub household: Option<i32>,
+    /// Print JSON instead of tables.
+    #[arg(long, global = true)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List tasks, the one not done for the longest first.
+    List {
+        /// List archived tasks instead of the others.
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Add a task.
+    Add {
+        name: String,
+        /// Id or name of a label of the task. Can be repeated.
+        #[arg(long, short)]
+        label: Vec<String>,
+    },
+    /// Record that a task was done now.
+    Done {
+        /// Id of the task, or a part of its name.
+        task: String,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Manage labels.
+    #[command(subcommand)]
+    Label(LabelCommand),
+    /// List households.
+    Households,
+    /// Add a user to the households nobody belongs to, like the one holding
+    /// tasks made before user accounts existed.
+    Adopt {
+        /// Name of the user.
+        user: String,
+    },
+    /// Open a full-screen UI of the tasks.
+    Tui,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LabelCommand {
+    /// List labels.
+    List,
+    /// Add a label.
+    Add {
+        name: String,
+        /// Color like `#90ee90`.
+        #[arg(long, default_value = DEFAULT_COLOR)]
+        color: String,
+    },
+    /// Rename or recolor a label.
+    Edit {
+        /// Id or name of the label.
+        label: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        color: Option<String>,
+    },
+    /// Delete a label. Its tasks are kept.
+    Delete {
+        /// Id or name of the label.
+        label: String,
+    },
+}
+
+/// Connects to the database of `Rocket.toml` like the server does, and
+/// runs the command. Returns what to print.
+pub async fn main(args: Args) -> Result<String, String> {
+    let mut figment = rocket::Config::figment().merge(("log_level", LogLevel::Off));
+    // Nothing is signed here, but Rocket doesn't start without a key outside
+    // of the debug profile.
+    if figment.find_value("secret_key").is_err() {
+        figment = figment.merge(("secret_key", hex::encode(rand::random::<[u8; 32]>())));
+    }
+    let rocket = rocket::custom(figment)
+        .attach(DbConn::fairing())
+        .attach(AdHoc::on_ignite("Database Migrations", run_db_migrations))
+        .ignite()
+        .await
+        .map_err(|e| e.to_string())?;
+    let conn = DbConn::get_one(&rocket)
+        .await
+        .ok_or("no database connection")?;
+    run(args, &conn).await
+}
+
+/// Runs the command against the database. Returns what to print.
+pub async fn run(args: Args, conn: &DbConn) -> Result<String, String> {
+    if let Command::Households = args.command {
+        let households = Household::all(conn).await;
+        return households_output(args.json, &households);
+    }
+    if let Command::Adopt { user } = &args.command {
+        let user = user.clone();
+        let user_id = conn
+            .run(move |c| User::id_by_name(&user, c))
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("No such user.")?;
+        let households = Household::adopt_orphans(user_id, conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        return households_output(args.json, &households);
+    }
+    let households = Household::all(conn).await;
+    let household = match args.household {
+        Some(id) => households
+            .into_iter()
+            .find(|h| h.id == id)
+            .ok_or(format!("No household {id}."))?,
+        None => households
+            .into_iter()
+            .next()
+            .ok_or("There are no households yet. Sign up on the web first.")?,
+    };
+    let household_id = household.id;
+    match args.command {
+        Command::List { archived } => {
+            let query = TaskQuery {
+                archived,
+                ..TaskQuery::default()
+            };
+            let tasks = Task::list(household_id, query, conn).await;
+            tasks_output(args.json, &tasks, household_id, conn).await
+        }
+        Command::Add { name, label } => {
+            let task_name = TaskName { name };
+            check(task_name.validate())?;
+            let labels = Label::all(household_id, conn).await;
+            let label_ids: Vec<i32> = label
+                .iter()
+                .map(|l| find_label(l, &labels).map(|l| l.id))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect();
+            let id = Task::insert_with_labels(task_name, label_ids, household_id, None, conn)
+                .await
+                .ok_or("Failed to add the task.")?;
+            let task = summary(id, household_id, conn).await?;
+            tasks_output(args.json, &[task], household_id, conn).await
+        }
+        Command::Done { task, note } => {
+            let tasks: Vec<Task> = Task::list(household_id, TaskQuery::default(), conn)
+                .await
+                .into_iter()
+                .map(|t| t.task)
+                .collect();
+            let id = find_task(&task, &tasks)?.id.ok_or("The task has no id.")?;
+            let note = note.filter(|n| !n.is_empty());
+            if !Task::update_to_today(id, household_id, note, None, conn).await {
+                return Err("Failed to record that the task was done.".to_string());
+            }
+            let task = summary(id, household_id, conn).await?;
+            tasks_output(args.json, &[task], household_id, conn).await
+        }
+        Command::Label(command) => label(command, args.json, household_id, conn).await,
+        Command::Tui => tui::run(household, conn).await.map(|()| String::new()),
+        Command::Households | Command::Adopt { .. } => {
+            unreachable!("handled without household")
+        }
+    }
+}
+
+async fn label(
+    command: LabelCommand,
+    json: bool,
+    household_id: i32,
+    conn: &DbConn,
+) -> Result<String, String> {
+    let labels = Label::all(household_id, conn).await;
+    let id = match command {
+        LabelCommand::List => return labels_output(json, &labels),
+        LabelCommand::Add { name, color } => {
+            let form = LabelForm { name, color };
+            check(form.validate())?;
+            Label::insert(form, household_id, None, conn)
+                .await
+                .ok_or("Failed to add the label.")?
+        }
+        LabelCommand::Edit { label, name, color } => {
+            let label = find_label(&label, &labels)?;
+            let id = label.id.ok_or("The label has no id.")?;
+            let form = LabelForm {
+                name: name.unwrap_or_else(|| label.name.clone()),
+                color: color.unwrap_or_else(|| label.color_hex.clone()),
+            };
+            check(form.validate())?;
+            if !Label::update(id, household_id, form, None, conn).await {
+                return Err("Failed to update the label.".to_string());
+            }
+            id
+        }
+        LabelCommand::Delete { label } => {
+            let label = find_label(&label, &labels)?;
+            let id = label.id.ok_or("The label has no id.")?;
+            if !Label::delete_with_id(id, household_id, None, conn).await {
+                return Err("Failed to delete the label.".to_string());
+            }
+            return labels_output(json, std::slice::from_ref(label));
+        }
+    };
+    let label = Label::label_by_id(id, household_id, conn)
+        .await
+        .map_err(|_| "Failed to read the label.")?;
+    labels_output(json, &[label])
+}
+
+async fn summary(id: i32, household_id: i32, conn: &DbConn) -> Result<TaskSummary, String> {
+    Task::summary_by_id(id, household_id, conn)
+        .await
+        .map_err(|_| "Failed to read the task.".to_string())
+}
+
+fn check(errors: Vec<FieldError>) -> Result<(), String> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        let messages: Vec<&str> = errors.iter().map(|e| e.message).collect();
+        Err(messages.join(" "))
+    }
+}
+
+/// Finds the task by id, or by its name ignoring case. A name is looked for
+/// as the whole name first, then as a part of it, then as letters appearing
+/// in it in order, like `vacbed` for "Vacuum the bedroom".
+pub fn find_task<'a>(query: &str, tasks: &'a [Task]) -> Result<&'a Task, String> {
+    if let Ok(id) = query.parse::<i32>()
+        && let Some(task) = tasks.iter().find(|t| t.id == Some(id))
+    {
+        return Ok(task);
+    }
+    let query = query.to_lowercase();
+    let names: Vec<String> = tasks.iter().map(|t| t.name.to_lowercase()).collect();
+    let matchers: [&dyn Fn(&str) -> bool; 3] = [
+        &|name| name == query,
+        &|name| name.contains(&query),
+        &|name| is_subsequence(&query, name),
+    ];
+    for matches in matchers {
+        let found: Vec<&Task> = tasks
+            .iter()
+            .zip(&names)
+            .filter(|(_, name)| matches(name))
+            .map(|(t, _)| t)
+            .collect();
+        match found[..] {
+            [] => continue,
+            [task] => return Ok(task),
+            _ => {
+                let candidates: Vec<String> = found
+                    .iter()
+                    .map(|t| format!("{} (id {})", t.name, t.id.unwrap_or_default()))
+                    .collect();
+                return Err(format!(
+                    "{} tasks match. Which one? {}",
+                    found.len(),
+                    candidates.join(", ")
+                ));
+            }
+        }
+    }
+    Err(format!("No task matches \"{query}\"."))
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|n| chars.any(|h| h == n))
+}
+
+/// Finds the label by id or by its exact name.
+fn find_label<'a>(query: &str, labels: &'a [Label]) -> Result<&'a Label, String> {
+    let id = query.parse::<i32>().ok();
+    labels
+        .iter()
+        .find(|l| id.is_some() && l.id == id)
+        .or_else(|| labels.iter().find(|l| l.name == query))
+        .ok_or_else(|| format!("No label \"{query}\"."))
+}
+
+fn output<T: Serialize + ?Sized>(
+    json: bool,
+    value: &T,
+    table: impl FnOnce() -> String,
+) -> Result<String, String> {
+    if json {
+        serde_json::to_string_pretty(value)
+            .map(|s| s + "\n")
+            .map_err(|e| e.to_string())
+    } else {
+        Ok(table())
+    }
+}
+
+async fn tasks_output(
+    json: bool,
+    tasks: &[TaskSummary],
+    household_id: i32,
+    conn: &DbConn,
+) -> Result<String, String> {
+    let label_names: HashMap<i32, String> = Label::all(household_id, conn)
+        .await
+        .into_iter()
+        .filter_map(|l| Some((l.id?, l.name)))
+        .collect();
+    output(json, tasks, || {
+        table(
+            &["ID", "NAME", "LAST DONE", "NEXT DUE", "LABELS"],
+            tasks
+                .iter()
+                .map(|t| {
+                    let next_due = match (t.next_due, t.status) {
+                        (Some(due), Some(DueStatus::Overdue)) => format!("{due} (overdue)"),
+                        (Some(due), _) => due.to_string(),
+                        (None, _) => "-".to_string(),
+                    };
+                    let labels: Vec<&str> = t
+                        .label_ids
+                        .iter()
+                        .filter_map(|id| label_names.get(id).map(String::as_str))
+                        .collect();
+                    vec![
+                        t.task.id.unwrap_or_default().to_string(),
+                        t.task.name.clone(),
+                        t.task.updated_at.format("%Y-%m-%d").to_string(),
+                        next_due,
+                        labels.join(", "),
+                    ]
+                })
+                .collect(),
+        )
+    })
+}
+
+fn households_output(json: bool, households: &[Household]) -> Result<String, String> {
+    output(json, households, || {
+        table(
+            &["ID", "NAME"],
+            households
+                .iter()
+                .map(|h| vec![h.id.to_string(), h.name.clone()])
+                .collect(),
+        )
+    })
+}
+
+fn labels_output(json: bool, labels: &[Label]) -> Result<String, String> {
+    output(json, labels, || {
+        table(
+            &["ID", "NAME", "COLOR"],
+            labels
+                .iter()
+                .map(|l| {
+                    vec![
+                        l.id.unwrap_or_default().to_string(),
+                        l.name.clone(),
+                        l.color_hex.clone(),
+                    ]
+                })
+                .collect(),
+        )
+    })
+}
+
+/// Lays out rows in columns as wide as their widest cell.
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let header = header.iter().map(|h| h.to_string()).collect();
+    let mut out = String::new();
+    for row in std::iter::once(header).chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:width$}"))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
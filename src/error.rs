@@ -0,0 +1,50 @@
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket_dyn_templates::{Template, context};
+
+/// Errors shown to the user as an error page.
+#[derive(Debug)]
+pub enum Error {
+    /// The requested task or label doesn't exist.
+    NotFound,
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(e: diesel::result::Error) -> Error {
+        match e {
+            diesel::result::Error::NotFound => Error::NotFound,
+            e => Error::Database(e),
+        }
+    }
+}
+
+impl Error {
+    pub fn status(&self) -> Status {
+        match self {
+            Error::NotFound => Status::NotFound,
+            Error::Database(_) => Status::InternalServerError,
+        }
+    }
+}
+
+/// Renders the error page for `status`.
+pub fn page(status: Status) -> Template {
+    let name = match status.code {
+        403 => "error/403",
+        404 => "error/404",
+        _ => "error/500",
+    };
+    Template::render(name, context! {})
+}
+
+impl<'r> Responder<'r, 'static> for Error {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if let Error::Database(ref e) = self {
+            log::error!("database error: {e}");
+        }
+        let status = self.status();
+        (status, page(status)).respond_to(req)
+    }
+}
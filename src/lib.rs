@@ -0,0 +1,186 @@
+#[macro_use]
+extern crate rocket;
+#[macro_use]
+extern crate diesel;
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+mod calendar;
+mod chart;
+pub mod cli;
+pub mod error;
+mod metrics;
+pub mod models;
+mod reminder;
+mod routes;
+#[cfg(test)]
+mod tests;
+mod trash;
+pub mod tui;
+mod webhook;
+
+#[cfg(not(feature = "postgres"))]
+use crate::diesel::connection::SimpleConnection;
+use rocket::fairing::AdHoc;
+use rocket::fs::{FileServer, relative};
+use rocket::{Build, Rocket};
+use rocket_dyn_templates::Template;
+use rocket_sync_db_pools::database;
+
+/// Connection to the database, SQLite unless the `postgres` feature is on.
+#[cfg(not(feature = "postgres"))]
+pub type DbConnection = diesel::SqliteConnection;
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::PgConnection;
+/// Backend of `DbConnection`, for boxed queries.
+pub type DbBackend = <DbConnection as diesel::Connection>::Backend;
+
+#[cfg_attr(not(feature = "postgres"), database("sqlite_database"))]
+#[cfg_attr(feature = "postgres", database("postgres_database"))]
+pub struct DbConn(DbConnection);
+
+pub async fn run_db_migrations(rocket: Rocket<Build>) -> Rocket<Build> {
+    use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+    #[cfg(not(feature = "postgres"))]
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+    #[cfg(feature = "postgres")]
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+    let conn = DbConn::get_one(&rocket).await.expect("database connection");
+
+    conn.run(|conn| {
+        conn.run_pending_migrations(MIGRATIONS)
+            .expect("diesel migrations");
+    })
+    .await;
+
+    #[cfg(not(feature = "postgres"))]
+    conn.run(|conn| {
+        conn.batch_execute("PRAGMA foreign_keys = ON")
+            .expect("Failed to enable foreign keys")
+    })
+    .await;
+
+    rocket
+}
+
+pub fn rocket() -> Rocket<Build> {
+    rocket::build()
+        .attach(DbConn::fairing())
+        .attach(AdHoc::on_ignite("Database Migrations", run_db_migrations))
+        .attach(metrics::Metrics::new())
+        .attach(AdHoc::on_liftoff("Email Reminders", |rocket| {
+            Box::pin(async move { reminder::start(rocket) })
+        }))
+        .attach(AdHoc::on_liftoff("Trash Purge", |rocket| {
+            Box::pin(async move { trash::start(rocket) })
+        }))
+        .attach(AdHoc::on_liftoff("Webhook Deliveries", |rocket| {
+            Box::pin(async move { webhook::start(rocket) })
+        }))
+        .attach(AdHoc::try_on_ignite("Webhooks", |rocket| async {
+            match rocket
+                .figment()
+                .extract_inner::<webhook::Config>("webhooks")
+            {
+                Ok(config) => Ok(rocket.manage(webhook::Webhooks::new(config))),
+                Err(e) if e.missing() => {
+                    Ok(rocket.manage(webhook::Webhooks::new(Default::default())))
+                }
+                Err(e) => {
+                    log::error!("invalid webhooks config: {e}");
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(Template::fairing())
+        .mount("/", FileServer::from(relative!("static")))
+        .register(
+            "/",
+            catchers![
+                routes::error::unauthorized,
+                routes::error::forbidden,
+                routes::error::not_found,
+                routes::error::internal_error
+            ],
+        )
+        .mount(
+            "/",
+            routes![
+                routes::task::index,
+                routes::task::new,
+                routes::task::update_date,
+                routes::task::update,
+                routes::task::edit,
+                routes::task::delete,
+                routes::task::confirm,
+                routes::task::by_label,
+                routes::task::search,
+                routes::task::archive,
+                routes::task::unarchive,
+                routes::trash::index,
+                routes::trash::restore,
+                routes::trash::purge,
+                routes::undo::undo,
+                routes::stats::index,
+                routes::activity::index,
+                routes::metrics::index,
+                routes::label::index,
+                routes::label::new,
+                routes::label::update,
+                routes::label::edit,
+                routes::label::confirm,
+                routes::label::delete,
+                routes::user::login_page,
+                routes::user::login,
+                routes::user::logout,
+                routes::user::signup_page,
+                routes::user::signup,
+                routes::user::set_email,
+                routes::household::index,
+                routes::household::new,
+                routes::household::switch,
+                routes::household::rotate_calendar_token,
+                routes::household::add_member,
+                routes::calendar::feed,
+                routes::calendar::label_feed,
+                routes::csv_file::export,
+                routes::csv_file::import_page,
+                routes::csv_file::import,
+                routes::backup::download,
+                routes::backup::index,
+                routes::backup::restore,
+                routes::webhook::index,
+                routes::webhook::new,
+                routes::webhook::delete
+            ],
+        )
+        .mount(
+            "/api/v1",
+            routes![
+                routes::api::task::list,
+                routes::api::task::get,
+                routes::api::task::create,
+                routes::api::task::update,
+                routes::api::task::delete,
+                routes::api::task::done,
+                routes::api::label::list,
+                routes::api::label::get,
+                routes::api::label::tasks,
+                routes::api::label::create,
+                routes::api::label::update,
+                routes::api::label::delete
+            ],
+        )
+        .register(
+            "/api/v1",
+            catchers![
+                routes::api::bad_request,
+                routes::api::unauthorized,
+                routes::api::forbidden,
+                routes::api::not_found,
+                routes::api::unprocessable,
+                routes::api::internal
+            ],
+        )
+}
@@ -0,0 +1,273 @@
+//! Prometheus metrics of tasks, and of HTTP requests collected by a fairing.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Local;
+use prometheus::{
+    GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::tokio::sync::Mutex;
+use rocket::{Build, Data, Request, Response, Rocket};
+
+use crate::DbConn;
+use crate::models::audit::Activity;
+use crate::models::completion::Completion;
+use crate::models::household::Household;
+use crate::models::label::Label;
+use crate::models::schedule::DueStatus;
+use crate::models::task::Task;
+use crate::models::webhook::Event;
+
+/// `[default.metrics]` table of `Rocket.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Token scrapers send as `Authorization: Bearer <token>`. Without it,
+    /// signed-in users can read the metrics of their households.
+    pub token: Option<String>,
+}
+
+/// Registry of the metrics, managed as state and attached as the fairing
+/// timing requests.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    tasks: IntGaugeVec,
+    overdue: IntGaugeVec,
+    oldest_age: GaugeVec,
+    completions: IntGaugeVec,
+    completions_total: IntCounterVec,
+    /// Held while the task metrics are read, so that scrapes don't mix.
+    scrape: Arc<Mutex<()>>,
+}
+
+/// When the request came, in the request-local cache.
+struct RequestStart(Instant);
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry =
+            Registry::new_custom(Some("last_date".to_string()), None).expect("valid metric prefix");
+        let household = ["household"];
+        let metrics = Metrics {
+            requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status."),
+                &["method", "route", "status"],
+            )
+            .expect("valid metric"),
+            latency: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time to respond to HTTP requests by route.",
+                ),
+                &["method", "route"],
+            )
+            .expect("valid metric"),
+            tasks: IntGaugeVec::new(
+                Opts::new(
+                    "tasks",
+                    "Tasks which are neither archived nor in the trash.",
+                ),
+                &household,
+            )
+            .expect("valid metric"),
+            overdue: IntGaugeVec::new(
+                Opts::new(
+                    "overdue_tasks",
+                    "Overdue tasks by label. Tasks without labels have an empty label.",
+                ),
+                &["household", "label"],
+            )
+            .expect("valid metric"),
+            oldest_age: GaugeVec::new(
+                Opts::new(
+                    "oldest_task_age_days",
+                    "Days since the task done the longest ago was done.",
+                ),
+                &household,
+            )
+            .expect("valid metric"),
+            completions: IntGaugeVec::new(
+                Opts::new(
+                    "completions",
+                    "Completions in the history of tasks, including archived ones.",
+                ),
+                &household,
+            )
+            .expect("valid metric"),
+            completions_total: IntCounterVec::new(
+                Opts::new("completions_total", "Times tasks were done."),
+                &household,
+            )
+            .expect("valid metric"),
+            registry,
+            scrape: Arc::new(Mutex::new(())),
+        };
+        let collectors: [Box<dyn prometheus::core::Collector>; 7] = [
+            Box::new(metrics.requests.clone()),
+            Box::new(metrics.latency.clone()),
+            Box::new(metrics.tasks.clone()),
+            Box::new(metrics.overdue.clone()),
+            Box::new(metrics.oldest_age.clone()),
+            Box::new(metrics.completions.clone()),
+            Box::new(metrics.completions_total.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+        metrics
+    }
+
+    /// Reads the task metrics from the database, and returns the metrics in
+    /// the Prometheus text format. With `households`, task metrics of other
+    /// households are left out.
+    pub async fn render(
+        &self,
+        households: Option<Vec<i32>>,
+        conn: &DbConn,
+    ) -> Result<String, String> {
+        let _scrape = self.scrape.lock().await;
+        let now = Local::now().naive_local();
+        // Households and labels gone since the last scrape disappear.
+        self.tasks.reset();
+        self.overdue.reset();
+        self.oldest_age.reset();
+        self.completions.reset();
+        let ids = match &households {
+            Some(ids) => ids.clone(),
+            None => Household::all(conn)
+                .await
+                .into_iter()
+                .map(|h| h.id)
+                .collect(),
+        };
+        for id in ids {
+            let label_names: HashMap<i32, String> = Label::all(id, conn)
+                .await
+                .into_iter()
+                .filter_map(|l| Some((l.id?, l.name)))
+                .collect();
+            let (tasks, completions, done) = conn
+                .run(move |c| {
+                    let tasks = Task::all_in(id, c)?;
+                    let ids: Vec<i32> = tasks.iter().filter_map(|t| t.id).collect();
+                    let completions = Completion::count_for(&ids, c)?;
+                    let tasks: Vec<Task> = tasks
+                        .into_iter()
+                        .filter(|t| t.archived_at.is_none())
+                        .collect();
+                    let done = Activity::count(id, Event::TaskCompleted, c)?;
+                    let tasks = Task::summarize(tasks, c)?;
+                    Ok::<_, diesel::result::Error>((tasks, completions, done))
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let household = id.to_string();
+            self.tasks
+                .with_label_values(&[&household])
+                .set(tasks.len() as i64);
+            self.completions
+                .with_label_values(&[&household])
+                .set(completions);
+            // Counted from the activity log, which only grows, so that the
+            // counter goes on from where it was after a restart.
+            let total = self.completions_total.with_label_values(&[&household]);
+            let done = u64::try_from(done).unwrap_or_default();
+            if done > total.get() {
+                total.inc_by(done - total.get());
+            }
+            for name in label_names.values() {
+                self.overdue.with_label_values(&[&household, name]).set(0);
+            }
+            for t in tasks
+                .iter()
+                .filter(|t| t.status == Some(DueStatus::Overdue))
+            {
+                let names: Vec<&str> = t
+                    .label_ids
+                    .iter()
+                    .filter_map(|id| label_names.get(id).map(String::as_str))
+                    .collect();
+                for name in if names.is_empty() { vec![""] } else { names } {
+                    self.overdue.with_label_values(&[&household, name]).inc();
+                }
+            }
+            if let Some(oldest) = tasks.iter().map(|t| t.task.updated_at).min() {
+                let days = (now - oldest).num_seconds() as f64 / (24 * 60 * 60) as f64;
+                self.oldest_age.with_label_values(&[&household]).set(days);
+            }
+        }
+        let mut families = self.registry.gather();
+        if let Some(ids) = households {
+            let ids: Vec<String> = ids.iter().map(i32::to_string).collect();
+            for family in &mut families {
+                family.mut_metric().retain(|m| {
+                    m.get_label()
+                        .iter()
+                        .filter(|l| l.name() == "household")
+                        .all(|l| ids.iter().any(|id| id == l.value()))
+                });
+            }
+            families.retain(|f| !f.get_metric().is_empty());
+        }
+        TextEncoder::new()
+            .encode_to_string(&families)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for Metrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Metrics",
+            kind: Kind::Ignite | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let config = match rocket.figment().extract_inner::<Config>("metrics") {
+            Ok(config) => config,
+            Err(e) if e.missing() => Config::default(),
+            Err(e) => {
+                log::error!("invalid metrics config: {e}");
+                return Err(rocket);
+            }
+        };
+        Ok(rocket.manage(self.clone()).manage(config))
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let start = req.local_cache(|| RequestStart(Instant::now())).0;
+        let method = req.method().as_str();
+        // Routes rather than paths, so that `/1` and `/2` count together.
+        let route = req
+            .route()
+            .map_or_else(|| "none".to_string(), |r| r.uri.to_string());
+        self.requests
+            .with_label_values(&[method, &route, &res.status().code.to_string()])
+            .inc();
+        self.latency
+            .with_label_values(&[method, &route])
+            .observe(start.elapsed().as_secs_f64());
+    }
+}
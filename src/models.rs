@@ -1,2 +1,17 @@
+pub mod audit;
+pub mod backup;
+pub mod change;
+pub mod completion;
+pub mod csv_file;
+pub mod household;
 pub mod label;
+pub mod reminder;
+pub mod schedule;
+pub mod search;
+pub mod stats;
 pub mod task;
+pub mod task_label;
+pub mod undo;
+pub mod user;
+pub mod validation;
+pub mod webhook;
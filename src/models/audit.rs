@@ -0,0 +1,298 @@
+//! Log of every change of tasks and labels, shown on `/activity`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Local, NaiveDateTime};
+use diesel::{self, prelude::*};
+use serde_json::{Map, Value, json};
+
+mod schema {
+    table! {
+        audit_events {
+            id -> Integer,
+            household_id -> Integer, // foreign key
+            user_id -> Nullable<Integer>, // foreign key
+            event -> Text,
+            entity_id -> Integer,
+            before -> Nullable<Text>,
+            after -> Nullable<Text>,
+            created_at -> Timestamp,
+        }
+    }
+}
+
+use self::schema::audit_events;
+
+use crate::models::label::Label;
+use crate::models::task::Task;
+use crate::models::task_label::TaskLabel;
+use crate::models::user::User;
+use crate::models::webhook::Event;
+use crate::{DbConn, DbConnection};
+
+/// How many changes `/activity` shows, the latest ones.
+pub const PAGE_SIZE: i64 = 200;
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_events)]
+struct NewAuditEvent {
+    household_id: i32,
+    user_id: Option<i32>,
+    event: &'static str,
+    entity_id: i32,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Queryable)]
+struct AuditEvent {
+    user_id: Option<i32>,
+    event: String,
+    entity_id: i32,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+/// What was changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum Entity {
+    Task,
+    Label,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entity::Task => "task",
+            Entity::Label => "label",
+        }
+    }
+}
+
+/// Filtering of the log, like `?event=task.updated&entity=task&id=3&user=1`.
+///
+/// Fields with invalid values are ignored.
+#[derive(FromForm, Serialize, Debug, Clone, Default)]
+pub struct ActivityQuery {
+    pub event: Option<Event>,
+    pub entity: Option<Entity>,
+    /// Only changes of the task or label with this id.
+    pub id: Option<i32>,
+    /// Only changes made by this user.
+    pub user: Option<i32>,
+}
+
+/// A logged change.
+#[derive(Serialize, Debug, Clone)]
+pub struct Activity {
+    pub created_at: NaiveDateTime,
+    /// Name of the user who made the change. `None` for changes made without
+    /// signing in, e.g. by `last-date-cli`, and by users deleted since.
+    pub actor: Option<String>,
+    pub event: String,
+    /// `task` or `label`.
+    pub entity: String,
+    pub entity_id: i32,
+    /// Name of the task or label at the time.
+    pub name: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+/// A field which differs before and after a change. Missing values are
+/// `None`, e.g. all of `before` for a new task.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl AuditEvent {
+    fn into_activity(self, names: &HashMap<i32, String>) -> Activity {
+        let before = parse(self.before.as_deref());
+        let after = parse(self.after.as_deref());
+        let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+        let changes = fields
+            .into_iter()
+            .map(|field| Change {
+                field: field.clone(),
+                before: before.get(field).and_then(display),
+                after: after.get(field).and_then(display),
+            })
+            .filter(|change| change.before != change.after)
+            .collect();
+        let name = after
+            .get("name")
+            .or_else(|| before.get("name"))
+            .and_then(display);
+        Activity {
+            created_at: self.created_at,
+            actor: self.user_id.and_then(|id| names.get(&id).cloned()),
+            entity: self.event.split('.').next().unwrap_or_default().to_string(),
+            event: self.event,
+            entity_id: self.entity_id,
+            name,
+            changes,
+        }
+    }
+}
+
+fn parse(json: Option<&str>) -> Map<String, Value> {
+    json.and_then(|j| serde_json::from_str(j).ok())
+        .unwrap_or_default()
+}
+
+/// Shows a logged value. Empty ones are `None`.
+fn display(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) if s.is_empty() => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) if values.is_empty() => None,
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(display)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+impl Activity {
+    /// Logs a change made by the user `actor`, if known. `before` and
+    /// `after` are the states of `task_state` or `label_state`.
+    pub(crate) fn record(
+        household_id: i32,
+        actor: Option<i32>,
+        event: Event,
+        entity_id: i32,
+        before: Option<Value>,
+        after: Option<Value>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        diesel::insert_into(audit_events::table)
+            .values(&NewAuditEvent {
+                household_id,
+                user_id: actor,
+                event: event.as_str(),
+                entity_id,
+                before: before.map(|v| v.to_string()),
+                after: after.map(|v| v.to_string()),
+                created_at: Local::now().naive_local(),
+            })
+            .execute(c)
+    }
+
+    /// What the log keeps of a task. Labels are kept by name, so that the
+    /// log still reads after they are deleted.
+    pub(crate) fn task_state(task: &Task, c: &mut DbConnection) -> QueryResult<Value> {
+        let id = task.id.expect("stored task has id");
+        let label_ids = TaskLabel::label_ids_of(&[id], c)?
+            .remove(&id)
+            .unwrap_or_default();
+        let mut labels: Vec<String> = Label::ids_by_name(task.household_id, c)?
+            .into_iter()
+            .filter(|(_, id)| label_ids.contains(id))
+            .map(|(name, _)| name)
+            .collect();
+        labels.sort();
+        let interval = task
+            .interval_count
+            .zip(task.interval_unit)
+            .map(|(count, unit)| format!("{count} {unit}"));
+        Ok(json!({
+            "name": task.name,
+            "description": task.description,
+            "updated_at": task.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+            "interval": interval,
+            "labels": labels,
+            "archived": task.archived_at.map(|d| d.format("%Y-%m-%d %H:%M").to_string()),
+        }))
+    }
+
+    /// What the log keeps of a label.
+    pub(crate) fn label_state(label: &Label) -> Value {
+        json!({
+            "name": label.name,
+            "color": label.color_hex,
+        })
+    }
+
+    /// Returns how many changes of `event` were logged in the household.
+    pub(crate) fn count(household_id: i32, event: Event, c: &mut DbConnection) -> QueryResult<i64> {
+        audit_events::table
+            .filter(audit_events::household_id.eq(household_id))
+            .filter(audit_events::event.eq(event.as_str()))
+            .count()
+            .get_result(c)
+    }
+
+    /// Returns changes in the household, the latest one first.
+    pub async fn list(household_id: i32, query: ActivityQuery, conn: &DbConn) -> Vec<Activity> {
+        conn.run(move |c| {
+            let mut events = audit_events::table
+                .filter(audit_events::household_id.eq(household_id))
+                .select((
+                    audit_events::user_id,
+                    audit_events::event,
+                    audit_events::entity_id,
+                    audit_events::before,
+                    audit_events::after,
+                    audit_events::created_at,
+                ))
+                .into_boxed();
+            if let Some(event) = query.event {
+                events = events.filter(audit_events::event.eq(event.as_str()));
+            }
+            if let Some(entity) = query.entity {
+                events = events.filter(audit_events::event.like(format!("{}.%", entity.as_str())));
+            }
+            if let Some(id) = query.id {
+                events = events.filter(audit_events::entity_id.eq(id));
+            }
+            if let Some(user) = query.user {
+                events = events.filter(audit_events::user_id.eq(user));
+            }
+            let events = events
+                .order((audit_events::created_at.desc(), audit_events::id.desc()))
+                .limit(PAGE_SIZE)
+                .load::<AuditEvent>(c)?;
+            let user_ids: Vec<i32> = events.iter().filter_map(|e| e.user_id).collect();
+            let names = User::names_by_id(&user_ids, c)?;
+            Ok::<_, diesel::result::Error>(
+                events
+                    .into_iter()
+                    .map(|e| e.into_activity(&names))
+                    .collect(),
+            )
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Returns ids and names of the users who made changes in the
+    /// household, sorted by name.
+    pub async fn actors(household_id: i32, conn: &DbConn) -> Vec<(i32, String)> {
+        conn.run(move |c| {
+            let user_ids: Vec<i32> = audit_events::table
+                .filter(audit_events::household_id.eq(household_id))
+                .select(audit_events::user_id)
+                .distinct()
+                .load::<Option<i32>>(c)?
+                .into_iter()
+                .flatten()
+                .collect();
+            let mut actors: Vec<(i32, String)> =
+                User::names_by_id(&user_ids, c)?.into_iter().collect();
+            actors.sort_by(|a, b| a.1.cmp(&b.1));
+            Ok::<_, diesel::result::Error>(actors)
+        })
+        .await
+        .unwrap_or_default()
+    }
+}
@@ -0,0 +1,307 @@
+//! Versioned JSON snapshots of a household, for moving it to another machine.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde_json::Value;
+
+use crate::models::audit::Activity;
+use crate::models::change;
+use crate::models::completion::Completion;
+use crate::models::label::{Label, LabelForm};
+use crate::models::schedule::IntervalUnit;
+use crate::models::task::{Task, TaskName};
+use crate::models::task_label::TaskLabel;
+use crate::models::webhook::Event;
+use crate::{DbConn, DbConnection};
+
+/// Version of the snapshot format written by `take`. Bump it when the format
+/// changes, and keep reading the older ones.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub version: u32,
+    pub labels: Vec<LabelRecord>,
+    pub tasks: Vec<TaskRecord>,
+}
+
+/// A label. `id` is only meaningful inside the snapshot.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LabelRecord {
+    pub id: i32,
+    pub name: String,
+    pub color_hex: String,
+}
+
+/// A task with its history. `id` and `label_ids` are only meaningful inside
+/// the snapshot.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskRecord {
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub updated_at: NaiveDateTime,
+    #[serde(default)]
+    pub interval_count: Option<i32>,
+    #[serde(default)]
+    pub interval_unit: Option<IntervalUnit>,
+    #[serde(default)]
+    pub label_ids: Vec<i32>,
+    /// The oldest one first.
+    #[serde(default)]
+    pub completions: Vec<CompletionRecord>,
+    #[serde(default)]
+    pub archived_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CompletionRecord {
+    pub completed_at: NaiveDateTime,
+    pub note: Option<String>,
+}
+
+/// How to restore a snapshot into a household.
+#[derive(FromFormField, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Only into a household without tasks and labels.
+    Empty,
+    /// Next to the existing tasks. Labels with the same name are shared.
+    Merge,
+}
+
+/// What a restore added.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Restored {
+    pub labels: usize,
+    pub tasks: usize,
+}
+
+#[derive(Debug)]
+pub enum RestoreError {
+    /// The file isn't a snapshot. Nothing was restored.
+    Invalid(Vec<String>),
+    /// The household has tasks or labels, and the mode was `Empty`.
+    NotEmpty,
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for RestoreError {
+    fn from(e: diesel::result::Error) -> Self {
+        RestoreError::Database(e)
+    }
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreError::Invalid(errors) => write!(f, "Invalid backup: {}", errors.join(" ")),
+            RestoreError::NotEmpty => f.write_str(
+                "The household already has tasks or labels. Choose merge to add the backup to them.",
+            ),
+            RestoreError::Database(_) => f.write_str("The server failed."),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Returns all labels and tasks of the household.
+    pub async fn take(household_id: i32, conn: &DbConn) -> Snapshot {
+        let labels = Label::all(household_id, conn).await;
+        let tasks = Task::all(household_id, conn).await;
+        let ids: Vec<i32> = tasks.iter().filter_map(|t| t.id).collect();
+        let (mut label_ids, completions) = conn
+            .run(move |c| {
+                Ok::<_, diesel::result::Error>((
+                    TaskLabel::label_ids_of(&ids, c)?,
+                    Completion::for_tasks(&ids, c)?,
+                ))
+            })
+            .await
+            .unwrap_or_default();
+        let mut history: HashMap<i32, Vec<CompletionRecord>> = HashMap::new();
+        for done in completions {
+            history
+                .entry(done.task_id)
+                .or_default()
+                .push(CompletionRecord {
+                    completed_at: done.completed_at,
+                    note: done.note,
+                });
+        }
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            labels: labels
+                .into_iter()
+                .filter_map(|l| {
+                    Some(LabelRecord {
+                        id: l.id?,
+                        name: l.name,
+                        color_hex: l.color_hex,
+                    })
+                })
+                .collect(),
+            tasks: tasks
+                .into_iter()
+                .filter_map(|t| {
+                    let id = t.id?;
+                    Some(TaskRecord {
+                        id,
+                        name: t.name,
+                        description: t.description,
+                        updated_at: t.updated_at,
+                        interval_count: t.interval_count,
+                        interval_unit: t.interval_unit,
+                        label_ids: label_ids.remove(&id).unwrap_or_default(),
+                        completions: history.remove(&id).unwrap_or_default(),
+                        archived_at: t.archived_at,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads a snapshot, checking its version before the rest of it.
+    pub fn parse(data: &str) -> Result<Snapshot, RestoreError> {
+        let invalid = |e: String| RestoreError::Invalid(vec![e]);
+        let value: Value = serde_json::from_str(data).map_err(|e| invalid(e.to_string()))?;
+        match value.get("version").and_then(Value::as_u64) {
+            Some(v) if v == u64::from(SNAPSHOT_VERSION) => {}
+            Some(v) => return Err(invalid(format!("Unsupported version {v}."))),
+            None => return Err(invalid("No version.".to_string())),
+        }
+        serde_json::from_value(value).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// Returns every problem which would stop the snapshot from restoring.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut label_ids = HashSet::new();
+        for l in &self.labels {
+            if !label_ids.insert(l.id) {
+                errors.push(format!("Label {}: Duplicate id.", l.id));
+            }
+            let form = LabelForm {
+                name: l.name.clone(),
+                color: l.color_hex.clone(),
+            };
+            for e in form.validate() {
+                errors.push(format!("Label {}: {}", l.id, e.message));
+            }
+        }
+        let mut task_ids = HashSet::new();
+        for t in &self.tasks {
+            if !task_ids.insert(t.id) {
+                errors.push(format!("Task {}: Duplicate id.", t.id));
+            }
+            let name = TaskName {
+                name: t.name.clone(),
+            };
+            for e in name.validate() {
+                errors.push(format!("Task {}: {}", t.id, e.message));
+            }
+            if t.interval_count.is_some() != t.interval_unit.is_some() {
+                errors.push(format!(
+                    "Task {}: Interval needs both count and unit.",
+                    t.id
+                ));
+            } else if t.interval_count.is_some_and(|n| n <= 0) {
+                errors.push(format!("Task {}: Interval must be positive.", t.id));
+            }
+            for id in t.label_ids.iter().filter(|id| !label_ids.contains(id)) {
+                errors.push(format!("Task {}: No label with id {id}.", t.id));
+            }
+        }
+        errors
+    }
+
+    /// Adds the labels and tasks of the snapshot to the household with new
+    /// ids. Either all of it is restored or nothing is. They are logged as
+    /// created by `actor`.
+    pub async fn restore(
+        self,
+        mode: RestoreMode,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Result<Restored, RestoreError> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return Err(RestoreError::Invalid(errors));
+        }
+        conn.run(move |c| c.transaction(|c| self.apply(mode, household_id, actor, c)))
+            .await
+    }
+
+    fn apply(
+        self,
+        mode: RestoreMode,
+        household_id: i32,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> Result<Restored, RestoreError> {
+        let existing = Label::ids_by_name(household_id, c)?;
+        if mode == RestoreMode::Empty && (!existing.is_empty() || Task::count(household_id, c)? > 0)
+        {
+            return Err(RestoreError::NotEmpty);
+        }
+
+        let mut restored = Restored {
+            labels: 0,
+            tasks: 0,
+        };
+        let mut new_label_ids = HashMap::new();
+        for l in self.labels {
+            let id = match existing.get(&l.name) {
+                Some(&id) => id,
+                None => {
+                    restored.labels += 1;
+                    let form = LabelForm {
+                        name: l.name,
+                        color: l.color_hex,
+                    };
+                    Label::insert_in(form, household_id, actor, c)?
+                }
+            };
+            new_label_ids.insert(l.id, id);
+        }
+
+        for t in self.tasks {
+            let task = Task {
+                id: None,
+                name: t.name,
+                description: t.description,
+                updated_at: t.updated_at,
+                interval_count: t.interval_count,
+                interval_unit: t.interval_unit,
+                household_id,
+                deleted_at: None,
+                archived_at: t.archived_at,
+            };
+            let id = Task::create(&task, c)?;
+            if t.completions.is_empty() {
+                Completion::record(id, task.updated_at, None, c)?;
+            }
+            for done in t.completions {
+                Completion::record(id, done.completed_at, done.note, c)?;
+            }
+            let label_ids: Vec<i32> = t.label_ids.iter().map(|id| new_label_ids[id]).collect();
+            TaskLabel::set_for_task(id, &label_ids, c)?;
+            let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+            change::record(
+                household_id,
+                actor,
+                Event::TaskCreated,
+                id,
+                None,
+                Some(after),
+                c,
+            )?;
+            restored.tasks += 1;
+        }
+        Ok(restored)
+    }
+}
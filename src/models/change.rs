@@ -0,0 +1,26 @@
+//! Changes of tasks and labels, which are logged on `/activity` and sent to
+//! webhooks.
+
+use diesel::QueryResult;
+use serde_json::Value;
+
+use crate::DbConnection;
+use crate::models::audit::Activity;
+use crate::models::webhook::{Event, Webhook};
+
+/// Logs a change made by the user `actor`, if known, and queues it for
+/// webhooks, in the transaction of the caller. `before` and `after` are the
+/// states of `Activity::task_state` or `Activity::label_state`.
+pub(crate) fn record(
+    household_id: i32,
+    actor: Option<i32>,
+    event: Event,
+    entity_id: i32,
+    before: Option<Value>,
+    after: Option<Value>,
+    c: &mut DbConnection,
+) -> QueryResult<usize> {
+    let recorded = Activity::record(household_id, actor, event, entity_id, before, after, c)?;
+    Webhook::enqueue(household_id, event, entity_id, c)?;
+    Ok(recorded)
+}
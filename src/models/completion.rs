@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{self, prelude::*};
+
+mod schema {
+    table! {
+        task_completions {
+            id -> Nullable<Integer>,
+            task_id -> Integer, // foreign key
+            completed_at -> Timestamp,
+            note -> Nullable<Text>,
+        }
+    }
+}
+
+use self::schema::task_completions;
+
+use crate::{DbConn, DbConnection};
+
+/// One "I did it" record of a task.
+#[derive(Identifiable, Serialize, Queryable, Insertable, Debug, Clone)]
+#[diesel(table_name = task_completions)]
+pub struct Completion {
+    pub id: Option<i32>,
+    pub task_id: i32,
+    pub completed_at: NaiveDateTime,
+    pub note: Option<String>,
+}
+
+impl Completion {
+    /// Returns completions of the given task, the latest one first.
+    pub async fn for_task(task_id: i32, conn: &DbConn) -> Vec<Completion> {
+        conn.run(move |c| {
+            task_completions::table
+                .filter(task_completions::task_id.eq(task_id))
+                .order(task_completions::completed_at.desc())
+                .load::<Completion>(c)
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    /// Returns the dates each of the given tasks was done, the oldest one
+    /// first.
+    pub async fn dates_of(task_ids: Vec<i32>, conn: &DbConn) -> HashMap<i32, Vec<NaiveDate>> {
+        let completions = conn
+            .run(move |c| Completion::for_tasks(&task_ids, c))
+            .await
+            .unwrap_or_default();
+        let mut dates: HashMap<i32, Vec<NaiveDate>> = HashMap::new();
+        for done in completions {
+            dates
+                .entry(done.task_id)
+                .or_default()
+                .push(done.completed_at.date());
+        }
+        dates
+    }
+
+    /// Returns completions of the given tasks, the oldest one first.
+    pub(crate) fn for_tasks(
+        task_ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<Completion>> {
+        task_completions::table
+            .filter(task_completions::task_id.eq_any(task_ids))
+            .order(task_completions::completed_at.asc())
+            .load::<Completion>(c)
+    }
+
+    /// Returns how many times the given tasks were done.
+    pub(crate) fn count_for(task_ids: &[i32], c: &mut DbConnection) -> QueryResult<i64> {
+        task_completions::table
+            .filter(task_completions::task_id.eq_any(task_ids))
+            .count()
+            .get_result(c)
+    }
+
+    /// Returns the id of the new completion.
+    pub(crate) fn record(
+        task_id: i32,
+        completed_at: NaiveDateTime,
+        note: Option<String>,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
+        let completion = Completion {
+            id: None,
+            task_id,
+            completed_at,
+            note,
+        };
+        diesel::insert_into(task_completions::table)
+            .values(&completion)
+            .returning(task_completions::id)
+            .get_result::<Option<i32>>(c)
+            .map(|id| id.expect("inserted completion has id"))
+    }
+
+    pub(crate) fn delete_with_id(
+        id: i32,
+        task_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        diesel::delete(
+            task_completions::table
+                .filter(task_completions::id.eq(id))
+                .filter(task_completions::task_id.eq(task_id)),
+        )
+        .execute(c)
+    }
+
+    /// Moves the latest completion of the task to `to`. Its note is kept
+    /// unless `note` replaces it.
+    pub(crate) fn move_latest(
+        task_id: i32,
+        to: NaiveDateTime,
+        note: Option<String>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let latest = task_completions::table
+            .filter(task_completions::task_id.eq(task_id))
+            .order(task_completions::completed_at.desc())
+            .select(task_completions::id)
+            .first::<Option<i32>>(c)
+            .optional()?
+            .flatten();
+        let completion = task_completions::table.filter(task_completions::id.eq(latest));
+        match note {
+            Some(note) => diesel::update(completion)
+                .set((
+                    task_completions::completed_at.eq(to),
+                    task_completions::note.eq(note),
+                ))
+                .execute(c),
+            None => diesel::update(completion)
+                .set(task_completions::completed_at.eq(to))
+                .execute(c),
+        }
+    }
+
+    /// Returns the date of the latest completion, which is what a task's
+    /// "last updated" date means.
+    pub(crate) fn latest(task_id: i32, c: &mut DbConnection) -> QueryResult<Option<NaiveDateTime>> {
+        task_completions::table
+            .filter(task_completions::task_id.eq(task_id))
+            .select(diesel::dsl::max(task_completions::completed_at))
+            .first::<Option<NaiveDateTime>>(c)
+    }
+
+    pub(crate) fn delete_for_task(task_id: i32, c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_completions::table.filter(task_completions::task_id.eq(task_id)))
+            .execute(c)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn delete_all(c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_completions::table).execute(c)
+    }
+}
@@ -0,0 +1,224 @@
+//! CSV export and import of tasks, for keeping a spreadsheet copy.
+
+use std::collections::HashMap;
+
+use chrono::Local;
+
+use diesel::prelude::*;
+
+use crate::models::label::{DEFAULT_COLOR, Label, LabelForm};
+use crate::models::schedule::IntervalUnit;
+use crate::models::task::{Task, TaskName, TaskUpdate};
+use crate::{DbConn, DbConnection};
+
+/// Label names in one cell are separated by this.
+const LABEL_SEPARATOR: char = ';';
+
+/// One line of the CSV file. Tasks without `id` are new ones.
+#[derive(Serialize, Deserialize, Debug)]
+struct Row {
+    #[serde(default)]
+    id: Option<i32>,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    updated_at: String,
+    #[serde(default)]
+    labels: String,
+    #[serde(default)]
+    interval_count: Option<i32>,
+    #[serde(default)]
+    interval_unit: String,
+}
+
+/// What happened to one line of an imported file.
+#[derive(Serialize, Debug)]
+pub struct RowReport {
+    pub line: u64,
+    pub name: String,
+    pub accepted: bool,
+    /// "created" or "updated" if accepted, otherwise why it was rejected.
+    pub message: String,
+}
+
+/// Returns all tasks of the household as CSV, with a header line. Archived
+/// tasks are included, so that the file is a complete copy.
+pub async fn export(household_id: i32, conn: &DbConn) -> String {
+    let label_names: HashMap<i32, String> = Label::all(household_id, conn)
+        .await
+        .into_iter()
+        .filter_map(|l| Some((l.id?, l.name)))
+        .collect();
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for t in Task::all_summaries(household_id, conn).await {
+        let labels: Vec<&str> = t
+            .label_ids
+            .iter()
+            .filter_map(|id| label_names.get(id).map(String::as_str))
+            .collect();
+        let row = Row {
+            id: t.task.id,
+            name: t.task.name,
+            description: t.task.description,
+            updated_at: t.task.updated_at.format("%Y-%m-%d").to_string(),
+            labels: labels.join(&LABEL_SEPARATOR.to_string()),
+            interval_count: t.task.interval_count,
+            interval_unit: t
+                .task
+                .interval_unit
+                .map(|u| u.to_string())
+                .unwrap_or_default(),
+        };
+        writer.serialize(row).expect("writing CSV to memory");
+    }
+    String::from_utf8(writer.into_inner().expect("writing CSV to memory"))
+        .expect("CSV of UTF-8 strings")
+}
+
+/// Creates or updates tasks from CSV in the format of `export`.
+///
+/// Each line is imported on its own, so a bad line doesn't stop the others.
+/// Changes are logged as made by `actor`.
+pub async fn import(
+    data: &str,
+    household_id: i32,
+    actor: Option<i32>,
+    conn: &DbConn,
+) -> Vec<RowReport> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![RowReport::new(1, String::new(), Err(e.to_string()))],
+    };
+
+    let mut reports = Vec::new();
+    for record in reader.records() {
+        let (line, row) = match record {
+            Ok(record) => (
+                record.position().map_or(0, |p| p.line()),
+                record.deserialize::<Row>(Some(&headers)),
+            ),
+            Err(e) => (e.position().map_or(0, |p| p.line()), Err(e)),
+        };
+        let report = match row {
+            Ok(row) => {
+                let name = row.name.clone();
+                let result = import_row(row, household_id, actor, conn).await;
+                RowReport::new(line, name, result)
+            }
+            Err(e) => RowReport::new(line, String::new(), Err(e.to_string())),
+        };
+        reports.push(report);
+    }
+    reports
+}
+
+/// Imports the row in one transaction, so that a rejected row leaves no
+/// labels or tasks behind.
+async fn import_row(
+    row: Row,
+    household_id: i32,
+    actor: Option<i32>,
+    conn: &DbConn,
+) -> Result<&'static str, String> {
+    let interval_unit = match row.interval_unit.as_str() {
+        "" => None,
+        unit => Some(unit.parse::<IntervalUnit>()?),
+    };
+    let task = TaskUpdate {
+        name: row.name,
+        description: row.description,
+        updated_at: row.updated_at,
+        label_ids: Vec::new(),
+        note: None,
+        correct_latest: false,
+        interval_count: row.interval_count,
+        interval_unit,
+    };
+    if let Some(e) = task.validate().first() {
+        return Err(e.message.to_string());
+    }
+    let label_names: Vec<String> = row
+        .labels
+        .split(LABEL_SEPARATOR)
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .collect();
+    let id = row.id;
+    conn.run(move |c| c.transaction(|c| save_row(id, task, label_names, household_id, actor, c)))
+        .await
+        .map_err(|e| match (e, id) {
+            (diesel::result::Error::NotFound, Some(id)) => format!("No task with id {id}."),
+            _ => "The server failed.".to_string(),
+        })
+}
+
+/// Creates the task, or updates the one with `id`, with the labels of the
+/// names. Unknown labels are created. Returns what was done.
+fn save_row(
+    id: Option<i32>,
+    mut task: TaskUpdate,
+    label_names: Vec<String>,
+    household_id: i32,
+    actor: Option<i32>,
+    c: &mut DbConnection,
+) -> QueryResult<&'static str> {
+    let current = match id {
+        Some(id) => Task::find(id, household_id, c)?.updated_at,
+        None => Local::now().naive_local(),
+    };
+    // The date of a row is when the task was last done, so an earlier one
+    // corrects the latest completion, e.g. the one made with a new task.
+    task.correct_latest = task.done_at().is_some_and(|d| d < current);
+
+    let mut labels = Label::ids_by_name(household_id, c)?;
+    for name in label_names {
+        let label_id = match labels.get(&name) {
+            Some(&id) => id,
+            None => {
+                let form = LabelForm {
+                    name: name.clone(),
+                    color: DEFAULT_COLOR.to_string(),
+                };
+                let id = Label::insert_in(form, household_id, actor, c)?;
+                labels.insert(name, id);
+                id
+            }
+        };
+        task.label_ids.push(label_id);
+    }
+
+    let (id, action) = match id {
+        Some(id) => (id, "updated"),
+        None => {
+            let name = TaskName {
+                name: task.name.clone(),
+            };
+            (
+                Task::insert_in(name, &[], household_id, actor, c)?,
+                "created",
+            )
+        }
+    };
+    Task::update_in(id, household_id, task, actor, c)?;
+    Ok(action)
+}
+
+impl RowReport {
+    fn new(line: u64, name: String, result: Result<&'static str, String>) -> RowReport {
+        let (accepted, message) = match result {
+            Ok(action) => (true, action.to_string()),
+            Err(reason) => (false, reason),
+        };
+        RowReport {
+            line,
+            name,
+            accepted,
+            message,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use diesel::{self, prelude::*};
+use rand::distr::{Alphanumeric, SampleString};
+
+mod schema {
+    table! {
+        households {
+            id -> Integer,
+            name -> Text,
+            calendar_token -> Nullable<Text>,
+        }
+    }
+
+    table! {
+        household_members (household_id, user_id) {
+            household_id -> Integer, // foreign key
+            user_id -> Integer, // foreign key
+        }
+    }
+
+    joinable!(household_members -> households (household_id));
+    allow_tables_to_appear_in_same_query!(households, household_members);
+}
+
+use self::schema::{household_members, households};
+
+use crate::models::user::User;
+use crate::models::validation::FieldError;
+use crate::{DbConn, DbConnection};
+
+/// A list of tasks and labels shared by its members.
+#[derive(Identifiable, Serialize, Queryable, Debug, Clone)]
+#[diesel(table_name = households)]
+pub struct Household {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = households)]
+struct NewHousehold<'a> {
+    name: &'a str,
+    calendar_token: String,
+}
+
+fn new_calendar_token() -> String {
+    Alphanumeric.sample_string(&mut rand::rng(), 32)
+}
+
+#[derive(FromForm, Deserialize)]
+pub struct HouseholdForm {
+    pub name: String,
+}
+
+#[derive(FromForm, Deserialize)]
+pub struct MemberForm {
+    pub user_name: String,
+}
+
+impl HouseholdForm {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "Please input household name."));
+        }
+        errors
+    }
+}
+
+impl Household {
+    /// Returns households the user belongs to, the oldest one first.
+    pub async fn for_user(user_id: i32, conn: &DbConn) -> Vec<Household> {
+        conn.run(move |c| {
+            households::table
+                .inner_join(household_members::table)
+                .filter(household_members::user_id.eq(user_id))
+                .select((households::id, households::name))
+                .order(households::id)
+                .load::<Household>(c)
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    /// Returns all households, the oldest one first.
+    pub async fn all(conn: &DbConn) -> Vec<Household> {
+        conn.run(|c| {
+            households::table
+                .select((households::id, households::name))
+                .order(households::id)
+                .load::<Household>(c)
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    /// Returns the household whose calendar feeds the token unlocks.
+    pub async fn by_calendar_token(token: String, conn: &DbConn) -> Option<Household> {
+        conn.run(move |c| {
+            households::table
+                .filter(households::calendar_token.eq(token))
+                .select((households::id, households::name))
+                .first::<Household>(c)
+                .ok()
+        })
+        .await
+    }
+
+    /// Returns the secret for calendar feeds. Households made before feeds
+    /// existed have none until one is made with `rotate_calendar_token`.
+    pub async fn calendar_token(id: i32, conn: &DbConn) -> Option<String> {
+        conn.run(move |c| {
+            households::table
+                .find(id)
+                .select(households::calendar_token)
+                .first::<Option<String>>(c)
+                .ok()
+                .flatten()
+        })
+        .await
+    }
+
+    /// Replaces the secret for calendar feeds with a new one, so that links
+    /// shared before stop working. Returns the new one.
+    pub async fn rotate_calendar_token(id: i32, conn: &DbConn) -> Option<String> {
+        conn.run(move |c| {
+            let token = new_calendar_token();
+            diesel::update(households::table.find(id))
+                .set(households::calendar_token.eq(&token))
+                .execute(c)
+                .ok()
+                .filter(|&n| n > 0)?;
+            Some(token)
+        })
+        .await
+    }
+
+    /// Returns names of the members.
+    pub async fn member_names(id: i32, conn: &DbConn) -> Vec<String> {
+        conn.run(move |c| {
+            let user_ids = household_members::table
+                .filter(household_members::household_id.eq(id))
+                .select(household_members::user_id)
+                .load::<i32>(c)?;
+            User::names_of(&user_ids, c)
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Returns every household with email addresses of its members.
+    pub(crate) fn all_with_emails(
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<(Household, Vec<String>)>> {
+        let all = households::table
+            .select((households::id, households::name))
+            .order(households::id)
+            .load::<Household>(c)?;
+        all.into_iter()
+            .map(|h| {
+                let user_ids = household_members::table
+                    .filter(household_members::household_id.eq(h.id))
+                    .select(household_members::user_id)
+                    .load::<i32>(c)?;
+                let emails = User::emails_of(&user_ids, c)?;
+                Ok((h, emails))
+            })
+            .collect()
+    }
+
+    /// Returns the id of the new household, which has the user as its member.
+    pub async fn insert(form: HouseholdForm, user_id: i32, conn: &DbConn) -> Option<i32> {
+        conn.run(move |c| {
+            c.transaction(|c| Household::create(form.name.trim(), user_id, c))
+                .ok()
+        })
+        .await
+    }
+
+    /// Adds the user with the given name. Returns `false` if there is no such
+    /// user.
+    pub async fn add_member(id: i32, user_name: String, conn: &DbConn) -> bool {
+        conn.run(move |c| {
+            let Ok(Some(user_id)) = User::id_by_name(&user_name, c) else {
+                return false;
+            };
+            diesel::insert_into(household_members::table)
+                .values((
+                    household_members::household_id.eq(id),
+                    household_members::user_id.eq(user_id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(c)
+                .is_ok()
+        })
+        .await
+    }
+
+    fn create(name: &str, user_id: i32, c: &mut DbConnection) -> QueryResult<i32> {
+        let id = diesel::insert_into(households::table)
+            .values(&NewHousehold {
+                name,
+                calendar_token: new_calendar_token(),
+            })
+            .returning(households::id)
+            .get_result::<i32>(c)?;
+        diesel::insert_into(household_members::table)
+            .values((
+                household_members::household_id.eq(id),
+                household_members::user_id.eq(user_id),
+            ))
+            .execute(c)?;
+        Ok(id)
+    }
+
+    /// Gives a new user a household of their own to put tasks in.
+    pub(crate) fn create_for(
+        user_id: i32,
+        user_name: &str,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
+        Household::create(&format!("{user_name}'s home"), user_id, c)
+    }
+
+    /// Adds the user to every household nobody belongs to, e.g. the one
+    /// holding tasks made before user accounts existed. Returns them.
+    pub async fn adopt_orphans(user_id: i32, conn: &DbConn) -> QueryResult<Vec<Household>> {
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let orphans = households::table
+                    .filter(diesel::dsl::not(households::id.eq_any(
+                        household_members::table.select(household_members::household_id),
+                    )))
+                    .select((households::id, households::name))
+                    .order(households::id)
+                    .load::<Household>(c)?;
+                let rows: Vec<_> = orphans
+                    .iter()
+                    .map(|h| {
+                        (
+                            household_members::household_id.eq(h.id),
+                            household_members::user_id.eq(user_id),
+                        )
+                    })
+                    .collect();
+                if !rows.is_empty() {
+                    diesel::insert_into(household_members::table)
+                        .values(&rows)
+                        .execute(c)?;
+                }
+                Ok(orphans)
+            })
+        })
+        .await
+    }
+
+    #[cfg(test)]
+    pub async fn delete_all(conn: &DbConn) -> bool {
+        conn.run(|c| {
+            c.transaction(|c| {
+                diesel::delete(household_members::table).execute(c)?;
+                diesel::delete(households::table).execute(c)
+            })
+            .is_ok()
+        })
+        .await
+    }
+}
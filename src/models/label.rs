@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
 use diesel::{self, prelude::*};
+use regex::Regex;
 
 mod schema {
     table! {
@@ -6,11 +10,19 @@ mod schema {
             id -> Nullable<Integer>,
             name -> Text,
             color_hex -> Text,
+            household_id -> Integer, // foreign key
         }
     }
 }
 
-use crate::DbConn;
+use crate::error::Error;
+use crate::models::audit::Activity;
+use crate::models::change;
+use crate::models::task::Task;
+use crate::models::task_label::TaskLabel;
+use crate::models::validation::FieldError;
+use crate::models::webhook::Event;
+use crate::{DbConn, DbConnection};
 
 use self::schema::labels;
 
@@ -20,18 +32,41 @@ pub struct Label {
     pub id: Option<i32>,
     pub name: String,
     pub color_hex: String,
+    pub household_id: i32,
 }
 
-#[derive(FromForm)]
+#[derive(FromForm, Deserialize)]
 pub struct LabelForm {
     pub name: String,
     pub color: String,
 }
 
+/// Color of labels made without choosing one, e.g. by CSV import.
+pub const DEFAULT_COLOR: &str = "#90ee90";
+
+static COLOR_HEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#[[:xdigit:]]{6}$").unwrap());
+
+impl LabelForm {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.is_empty() {
+            errors.push(FieldError::new("name", "Please input label name."));
+        }
+        if !COLOR_HEX.is_match(&self.color) {
+            errors.push(FieldError::new(
+                "color",
+                "Please input label color with hex format.",
+            ));
+        }
+        errors
+    }
+}
+
 impl Label {
-    pub async fn all(conn: &DbConn) -> Vec<Label> {
-        conn.run(|c| {
+    pub async fn all(household_id: i32, conn: &DbConn) -> Vec<Label> {
+        conn.run(move |c| {
             labels::table
+                .filter(labels::household_id.eq(household_id))
                 .order(labels::name)
                 .load::<Label>(c)
                 .unwrap_or_default()
@@ -39,60 +74,211 @@ impl Label {
         .await
     }
 
-    pub async fn label_by_id(id: i32, conn: &DbConn) -> Label {
-        // TODO: avoid cloning `String`: could be slow
+    pub async fn label_by_id(id: i32, household_id: i32, conn: &DbConn) -> Result<Label, Error> {
+        conn.run(move |c| Label::find(id, household_id, c))
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Returns the id of the new label. `actor` is the user who added it, if
+    /// known, for the activity log.
+    pub async fn insert(
+        label_info: LabelForm,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Option<i32> {
         conn.run(move |c| {
-            labels::table
-                .filter(labels::id.eq(id))
-                .load::<Label>(c)
-                .unwrap()
-                .first()
-                .unwrap()
-                .clone()
+            c.transaction(|c| Label::insert_in(label_info, household_id, actor, c))
+                .ok()
         })
         .await
     }
 
-    pub async fn insert(label_info: LabelForm, conn: &DbConn) -> bool {
-        conn.run(|c| {
-            let l = Label {
-                id: None,
-                name: label_info.name,
-                color_hex: label_info.color,
-            };
-            diesel::insert_into(labels::table)
-                .values(&l)
-                .execute(c)
-                .is_ok()
-        })
-        .await
+    /// Same as `insert`, in the transaction of the caller.
+    pub(crate) fn insert_in(
+        label_info: LabelForm,
+        household_id: i32,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
+        let id = Label::create(label_info, household_id, c)?;
+        let after = Activity::label_state(&Label::find(id, household_id, c)?);
+        change::record(
+            household_id,
+            actor,
+            Event::LabelCreated,
+            id,
+            None,
+            Some(after),
+            c,
+        )?;
+        Ok(id)
+    }
+
+    /// Returns the id of the new label.
+    pub(crate) fn create(
+        label_info: LabelForm,
+        household_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
+        let l = Label {
+            id: None,
+            name: label_info.name,
+            color_hex: label_info.color,
+            household_id,
+        };
+        diesel::insert_into(labels::table)
+            .values(&l)
+            .returning(labels::id)
+            .get_result::<Option<i32>>(c)
+            .map(|id| id.expect("inserted label has id"))
     }
 
-    pub async fn update(id: i32, label: LabelForm, conn: &DbConn) -> bool {
+    pub async fn update(
+        id: i32,
+        household_id: i32,
+        label: LabelForm,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> bool {
         conn.run(move |c| {
-            diesel::update(labels::table.filter(labels::id.eq(id)))
-                .set((
-                    labels::name.eq(label.name),
-                    labels::color_hex.eq(label.color),
-                ))
-                .execute(c)
-                .is_ok()
+            c.transaction(|c| {
+                let before = Activity::label_state(&Label::find(id, household_id, c)?);
+                diesel::update(labels::table.filter(labels::id.eq(id)))
+                    .set((
+                        labels::name.eq(label.name),
+                        labels::color_hex.eq(label.color),
+                    ))
+                    .execute(c)?;
+                let after = Activity::label_state(&Label::find(id, household_id, c)?);
+                change::record(
+                    household_id,
+                    actor,
+                    Event::LabelUpdated,
+                    id,
+                    Some(before),
+                    Some(after),
+                    c,
+                )
+            })
+            .is_ok()
         })
         .await
     }
 
-    pub async fn delete_with_id(id: i32, conn: &DbConn) -> bool {
+    pub(crate) fn find(id: i32, household_id: i32, c: &mut DbConnection) -> QueryResult<Label> {
+        labels::table
+            .filter(labels::id.eq(id))
+            .filter(labels::household_id.eq(household_id))
+            .first::<Label>(c)
+    }
+
+    /// Returns ids of the labels of the household by their names.
+    pub(crate) fn ids_by_name(
+        household_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<HashMap<String, i32>> {
+        labels::table
+            .filter(labels::household_id.eq(household_id))
+            .select((labels::name, labels::id))
+            .load::<(String, Option<i32>)>(c)
+            .map(|pairs| {
+                pairs
+                    .into_iter()
+                    .filter_map(|(name, id)| Some((name, id?)))
+                    .collect()
+            })
+    }
+
+    /// Returns ones of the given ids which exist in the household.
+    pub(crate) fn existing_ids(
+        ids: &[i32],
+        household_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<i32>> {
+        labels::table
+            .filter(labels::id.eq_any(ids))
+            .filter(labels::household_id.eq(household_id))
+            .select(labels::id)
+            .load::<Option<i32>>(c)
+            .map(|ids| ids.into_iter().flatten().collect())
+    }
+
+    pub async fn delete_with_id(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> bool {
+        Label::take(id, household_id, actor, conn).await.is_ok()
+    }
+
+    /// Deletes the label. Returns it with ids of the tasks it was on, or
+    /// `Error::NotFound` if the household has no such label.
+    pub async fn take(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Result<(Label, Vec<i32>), Error> {
         conn.run(move |c| {
-            diesel::delete(labels::table.filter(labels::id.eq(id)))
-                .execute(c)
-                .is_ok()
+            c.transaction(|c| {
+                let label = Label::find(id, household_id, c)?;
+                let task_ids = TaskLabel::task_ids_of(id, c)?;
+                TaskLabel::delete_for_label(id, c)?;
+                diesel::delete(labels::table.filter(labels::id.eq(id))).execute(c)?;
+                let before = Activity::label_state(&label);
+                change::record(
+                    household_id,
+                    actor,
+                    Event::LabelDeleted,
+                    id,
+                    Some(before),
+                    None,
+                    c,
+                )?;
+                Ok::<_, diesel::result::Error>((label, task_ids))
+            })
         })
         .await
+        .map_err(Error::from)
+    }
+
+    /// Puts a label deleted by `take` back with its id, on the tasks which
+    /// are still there. It's logged as created by `actor`.
+    pub(crate) fn put_back(
+        label: &Label,
+        task_ids: &[i32],
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let id = label.id.expect("taken label has id");
+        diesel::insert_into(labels::table)
+            .values(label)
+            .execute(c)?;
+        let task_ids = Task::existing_ids(task_ids, label.household_id, c)?;
+        TaskLabel::add_to_tasks(id, &task_ids, c)?;
+        change::record(
+            label.household_id,
+            actor,
+            Event::LabelCreated,
+            id,
+            None,
+            Some(Activity::label_state(label)),
+            c,
+        )
     }
 
     #[cfg(test)]
     pub async fn delete_all(conn: &DbConn) -> bool {
-        conn.run(|c| diesel::delete(labels::table).execute(c).is_ok())
-            .await
+        conn.run(|c| {
+            c.transaction(|c| {
+                TaskLabel::delete_all(c)?;
+                diesel::delete(labels::table).execute(c)
+            })
+            .is_ok()
+        })
+        .await
     }
 }
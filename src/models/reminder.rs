@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use diesel::{self, prelude::*};
+
+mod schema {
+    table! {
+        task_reminders (task_id, updated_at) {
+            task_id -> Integer, // foreign key
+            updated_at -> Timestamp,
+            sent_at -> Timestamp,
+        }
+    }
+}
+
+use self::schema::task_reminders;
+
+use crate::DbConnection;
+
+/// Record of an email reminding of a task, keyed by the task's last updated
+/// date then. Doing the task again makes it remindable again.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = task_reminders)]
+pub struct Reminder {
+    pub task_id: i32,
+    pub updated_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+}
+
+impl Reminder {
+    /// Returns `(task_id, updated_at)` pairs already reminded of among the
+    /// given tasks.
+    pub(crate) fn sent(
+        task_ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<HashSet<(i32, NaiveDateTime)>> {
+        task_reminders::table
+            .filter(task_reminders::task_id.eq_any(task_ids))
+            .select((task_reminders::task_id, task_reminders::updated_at))
+            .load::<(i32, NaiveDateTime)>(c)
+            .map(|pairs| pairs.into_iter().collect())
+    }
+
+    pub(crate) fn record(reminders: &[Reminder], c: &mut DbConnection) -> QueryResult<usize> {
+        let mut recorded = 0;
+        for r in reminders {
+            recorded += diesel::insert_into(task_reminders::table)
+                .values(r)
+                .on_conflict_do_nothing()
+                .execute(c)?;
+        }
+        Ok(recorded)
+    }
+
+    pub(crate) fn delete_for_task(task_id: i32, c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_reminders::table.filter(task_reminders::task_id.eq(task_id))).execute(c)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn delete_all(c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_reminders::table).execute(c)
+    }
+}
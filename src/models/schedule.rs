@@ -0,0 +1,184 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Days, Months, NaiveDate};
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use rocket::form::{self, FromFormField, ValueField};
+use serde::Serializer;
+
+/// Tasks due within this number of days are "due soon".
+const DUE_SOON_DAYS: i64 = 3;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromFormField,
+    AsExpression,
+    FromSqlRow,
+)]
+#[serde(rename_all = "lowercase")]
+#[diesel(sql_type = Text)]
+pub enum IntervalUnit {
+    Days,
+    Weeks,
+    Months,
+}
+
+impl IntervalUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntervalUnit::Days => "days",
+            IntervalUnit::Weeks => "weeks",
+            IntervalUnit::Months => "months",
+        }
+    }
+
+    /// Returns the date `count` units after `date`.
+    pub fn add_to(&self, date: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            IntervalUnit::Days => date.checked_add_days(Days::new(count.into())),
+            IntervalUnit::Weeks => date.checked_add_days(Days::new(7 * u64::from(count))),
+            IntervalUnit::Months => date.checked_add_months(Months::new(count)),
+        }
+    }
+
+    /// Returns the date `count` units before `date`.
+    pub fn subtract_from(&self, date: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            IntervalUnit::Days => date.checked_sub_days(Days::new(count.into())),
+            IntervalUnit::Weeks => date.checked_sub_days(Days::new(7 * u64::from(count))),
+            IntervalUnit::Months => date.checked_sub_months(Months::new(count)),
+        }
+    }
+
+    fn suffix(&self) -> char {
+        match self {
+            IntervalUnit::Days => 'd',
+            IntervalUnit::Weeks => 'w',
+            IntervalUnit::Months => 'm',
+        }
+    }
+}
+
+impl fmt::Display for IntervalUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IntervalUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "days" => Ok(IntervalUnit::Days),
+            "weeks" => Ok(IntervalUnit::Weeks),
+            "months" => Ok(IntervalUnit::Months),
+            _ => Err(format!("unknown interval unit: {s}")),
+        }
+    }
+}
+
+/// A length of time written like `30d`, `2w` or `6m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub count: u32,
+    pub unit: IntervalUnit,
+}
+
+impl Period {
+    /// Returns the date this period before `date`.
+    pub fn before(&self, date: NaiveDate) -> Option<NaiveDate> {
+        self.unit.subtract_from(date, self.count)
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.count, self.unit.suffix())
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid period: {s}");
+        let (last, _) = s.char_indices().last().ok_or_else(invalid)?;
+        let (count, unit) = s.split_at(last);
+        let unit = match unit {
+            "d" => IntervalUnit::Days,
+            "w" => IntervalUnit::Weeks,
+            "m" => IntervalUnit::Months,
+            _ => return Err(invalid()),
+        };
+        let count = count.parse().map_err(|_| invalid())?;
+        Ok(Period { count, unit })
+    }
+}
+
+impl serde::Serialize for Period {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[rocket::async_trait]
+impl<'v> FromFormField<'v> for Period {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|e: String| form::Error::validation(e).into())
+    }
+}
+
+impl<DB> ToSql<Text, DB> for IntervalUnit
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for IntervalUnit
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(String::from_sql(bytes)?.parse()?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DueStatus {
+    Overdue,
+    DueSoon,
+    Ok,
+}
+
+impl DueStatus {
+    pub fn of(next_due: NaiveDate, today: NaiveDate) -> DueStatus {
+        let days_left = (next_due - today).num_days();
+        if days_left < 0 {
+            DueStatus::Overdue
+        } else if days_left <= DUE_SOON_DAYS {
+            DueStatus::DueSoon
+        } else {
+            DueStatus::Ok
+        }
+    }
+}
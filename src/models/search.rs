@@ -0,0 +1,154 @@
+//! Full-text search of tasks with the `tasks_fts` table of SQLite, or the
+//! `search_vector` column of PostgreSQL.
+
+use std::collections::HashMap;
+
+use diesel::sql_types::{Integer, Text};
+use diesel::{self, prelude::*};
+
+use crate::DbConn;
+use crate::models::task::{Task, TaskSummary};
+
+/// Marks the start of a match in highlighted text.
+const MATCH_START: char = '\u{1}';
+/// Marks the end of a match.
+const MATCH_END: char = '\u{2}';
+/// Words of the description shown around matches.
+const SNIPPET_WORDS: i32 = 16;
+
+/// A piece of highlighted text, which is a match or text between matches.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// A task found, with its name and a part of its description highlighted.
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub summary: TaskSummary,
+    pub name_fragments: Vec<Fragment>,
+    pub description_fragments: Vec<Fragment>,
+}
+
+#[derive(QueryableByName)]
+struct Hit {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    description: String,
+}
+
+/// Returns the words of user input. Anything else is dropped, so that the
+/// words can't be read as query syntax.
+fn words(q: &str) -> Vec<&str> {
+    q.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Turns user input into an FTS5 query which finds tasks having words
+/// starting with each of the input words. Returns `None` if there are no
+/// words.
+#[cfg(not(feature = "postgres"))]
+fn text_query(q: &str) -> Option<String> {
+    let words: Vec<String> = words(q).iter().map(|w| format!("\"{w}\"*")).collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Turns user input into a `tsquery` which finds tasks having words
+/// starting with each of the input words. Returns `None` if there are no
+/// words.
+#[cfg(feature = "postgres")]
+fn text_query(q: &str) -> Option<String> {
+    let words: Vec<String> = words(q).iter().map(|w| format!("{w}:*")).collect();
+    (!words.is_empty()).then(|| words.join(" & "))
+}
+
+/// Query of tasks matching the text query bound first, in the household
+/// bound second.
+#[cfg(not(feature = "postgres"))]
+fn search_sql() -> String {
+    format!(
+        "SELECT tasks.id AS id, \
+             highlight(tasks_fts, 0, char(1), char(2)) AS name, \
+             snippet(tasks_fts, 1, char(1), char(2), '...', {SNIPPET_WORDS}) AS description \
+         FROM tasks_fts JOIN tasks ON tasks.id = tasks_fts.rowid \
+         WHERE tasks_fts MATCH ? AND tasks.household_id = ? AND tasks.deleted_at IS NULL \
+         ORDER BY rank"
+    )
+}
+
+#[cfg(feature = "postgres")]
+fn search_sql() -> String {
+    let marks = "'StartSel=' || chr(1) || ', StopSel=' || chr(2)";
+    let min_words = SNIPPET_WORDS / 2;
+    format!(
+        "SELECT tasks.id AS id, \
+             ts_headline('simple', tasks.name, query, 'HighlightAll=true, ' || {marks}) AS name, \
+             ts_headline('simple', tasks.description, query, \
+                 'MaxWords={SNIPPET_WORDS}, MinWords={min_words}, ' || {marks}) AS description \
+         FROM tasks, to_tsquery('simple', $1) AS query \
+         WHERE tasks.search_vector @@ query AND tasks.household_id = $2 AND tasks.deleted_at IS NULL \
+         ORDER BY ts_rank(tasks.search_vector, query) DESC, tasks.id"
+    )
+}
+
+/// Splits text marked by `MATCH_START` and `MATCH_END` into fragments.
+fn fragments(marked: &str) -> Vec<Fragment> {
+    let mut fragments = Vec::new();
+    for (i, part) in marked.split(MATCH_START).enumerate() {
+        let (matched, rest) = match part.split_once(MATCH_END) {
+            // Text before the first match has no end mark.
+            Some((matched, rest)) if i > 0 => (matched, rest),
+            _ => ("", part),
+        };
+        for (text, matched) in [(matched, true), (rest, false)] {
+            if !text.is_empty() {
+                fragments.push(Fragment {
+                    text: text.to_string(),
+                    matched,
+                });
+            }
+        }
+    }
+    fragments
+}
+
+impl SearchResult {
+    /// Returns tasks of the household matching the words of `q`, the most
+    /// relevant one first.
+    pub async fn find(q: String, household_id: i32, conn: &DbConn) -> Vec<SearchResult> {
+        let Some(query) = text_query(&q) else {
+            return Vec::new();
+        };
+        conn.run(move |c| {
+            let hits = diesel::sql_query(search_sql())
+                .bind::<Text, _>(query)
+                .bind::<Integer, _>(household_id)
+                .load::<Hit>(c)?;
+            let ids: Vec<i32> = hits.iter().map(|h| h.id).collect();
+            let mut summaries: HashMap<i32, TaskSummary> =
+                Task::summarize(Task::by_ids(&ids, c)?, c)?
+                    .into_iter()
+                    .filter_map(|t| Some((t.task.id?, t)))
+                    .collect();
+            Ok::<_, diesel::result::Error>(
+                hits.into_iter()
+                    .filter_map(|h| {
+                        Some(SearchResult {
+                            summary: summaries.remove(&h.id)?,
+                            name_fragments: fragments(&h.name),
+                            description_fragments: fragments(&h.description),
+                        })
+                    })
+                    .collect(),
+            )
+        })
+        .await
+        .unwrap_or_default()
+    }
+}
@@ -0,0 +1,221 @@
+//! How often tasks are done, from their completion history.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Local, NaiveDate};
+
+use crate::DbConn;
+use crate::models::completion::Completion;
+use crate::models::label::Label;
+use crate::models::task::Task;
+use crate::models::task_label::TaskLabel;
+
+/// Number of months of the completion counts per label.
+const MONTHS: usize = 12;
+
+/// Intervals between completions of a task, in days.
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskStats {
+    pub id: i32,
+    pub name: String,
+    pub label_ids: Vec<i32>,
+    pub completions: usize,
+    pub mean_interval: Option<f64>,
+    pub median_interval: Option<f64>,
+    pub max_interval: Option<i64>,
+    /// Days since it was done last.
+    pub current_gap: i64,
+    /// `current_gap` divided by `median_interval`. Over 1 means it's been
+    /// longer than usual.
+    pub gap_ratio: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LabelStats {
+    pub label: Label,
+    pub tasks: usize,
+    pub completions: usize,
+    /// Mean `gap_ratio` of its tasks.
+    pub gap_ratio: Option<f64>,
+    /// Completions of its tasks in each of `Stats::months`.
+    pub monthly: Vec<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Stats {
+    /// Like `2026-10`, the oldest one first.
+    pub months: Vec<String>,
+    /// The one longest overdue compared with its usual interval first.
+    pub tasks: Vec<TaskStats>,
+    pub labels: Vec<LabelStats>,
+    /// The label with the highest `gap_ratio`.
+    pub most_neglected: Option<i32>,
+}
+
+fn mean(values: &[i64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<i64>() as f64 / values.len() as f64)
+}
+
+fn median(values: &[i64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let n = sorted.len();
+    match n {
+        0 => None,
+        _ if n % 2 == 1 => Some(sorted[n / 2] as f64),
+        _ => Some((sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0),
+    }
+}
+
+/// Returns the first days of the `MONTHS` months up to the one of `today`.
+fn months_until(today: NaiveDate) -> Vec<NaiveDate> {
+    let mut month = today.with_day(1).expect("first day of month");
+    let mut months = vec![month];
+    for _ in 1..MONTHS {
+        month = month
+            .pred_opt()
+            .and_then(|d| d.with_day(1))
+            .expect("previous month");
+        months.push(month);
+    }
+    months.reverse();
+    months
+}
+
+impl TaskStats {
+    /// `dates` are the dates the task was done, the oldest one first.
+    pub fn new(
+        task: &Task,
+        label_ids: Vec<i32>,
+        dates: &[NaiveDate],
+        today: NaiveDate,
+    ) -> TaskStats {
+        let intervals: Vec<i64> = dates.windows(2).map(|w| (w[1] - w[0]).num_days()).collect();
+        let last = dates.last().copied().unwrap_or(task.updated_at.date());
+        let current_gap = (today - last).num_days();
+        let median_interval = median(&intervals);
+        TaskStats {
+            id: task.id.unwrap_or_default(),
+            name: task.name.clone(),
+            label_ids,
+            completions: dates.len(),
+            mean_interval: mean(&intervals),
+            median_interval,
+            max_interval: intervals.iter().copied().max(),
+            current_gap,
+            gap_ratio: median_interval
+                .filter(|&m| m > 0.0)
+                .map(|m| current_gap as f64 / m),
+        }
+    }
+}
+
+impl Stats {
+    pub async fn of(household_id: i32, conn: &DbConn) -> Stats {
+        let labels = Label::all(household_id, conn).await;
+        let tasks = Task::all(household_id, conn).await;
+        let ids: Vec<i32> = tasks.iter().filter_map(|t| t.id).collect();
+        let (label_ids, completions) = conn
+            .run(move |c| {
+                Ok::<_, diesel::result::Error>((
+                    TaskLabel::label_ids_of(&ids, c)?,
+                    Completion::for_tasks(&ids, c)?,
+                ))
+            })
+            .await
+            .unwrap_or_default();
+        Stats::compute(
+            &tasks,
+            label_ids,
+            &completions,
+            labels,
+            Local::now().date_naive(),
+        )
+    }
+
+    /// `completions` are the oldest one first.
+    pub fn compute(
+        tasks: &[Task],
+        mut label_ids: HashMap<i32, Vec<i32>>,
+        completions: &[Completion],
+        labels: Vec<Label>,
+        today: NaiveDate,
+    ) -> Stats {
+        let mut dates: HashMap<i32, Vec<NaiveDate>> = HashMap::new();
+        for done in completions {
+            dates
+                .entry(done.task_id)
+                .or_default()
+                .push(done.completed_at.date());
+        }
+        let mut task_stats: Vec<TaskStats> = tasks
+            .iter()
+            .filter_map(|t| {
+                let id = t.id?;
+                let dates = dates.get(&id).map(Vec::as_slice).unwrap_or_default();
+                Some(TaskStats::new(
+                    t,
+                    label_ids.remove(&id).unwrap_or_default(),
+                    dates,
+                    today,
+                ))
+            })
+            .collect();
+        task_stats.sort_by(|a, b| {
+            b.gap_ratio
+                .unwrap_or(-1.0)
+                .total_cmp(&a.gap_ratio.unwrap_or(-1.0))
+        });
+
+        let months = months_until(today);
+        let month_index = |date: NaiveDate| months.iter().rposition(|&m| m <= date);
+        let label_stats: Vec<LabelStats> = labels
+            .into_iter()
+            .map(|label| {
+                let tasks: Vec<&TaskStats> = task_stats
+                    .iter()
+                    .filter(|t| label.id.is_some_and(|id| t.label_ids.contains(&id)))
+                    .collect();
+                let ratios: Vec<f64> = tasks.iter().filter_map(|t| t.gap_ratio).collect();
+                let mut monthly = vec![0; months.len()];
+                for t in &tasks {
+                    for &date in dates.get(&t.id).map(Vec::as_slice).unwrap_or_default() {
+                        if let Some(i) = month_index(date) {
+                            monthly[i] += 1;
+                        }
+                    }
+                }
+                LabelStats {
+                    tasks: tasks.len(),
+                    completions: tasks.iter().map(|t| t.completions).sum(),
+                    gap_ratio: (!ratios.is_empty())
+                        .then(|| ratios.iter().sum::<f64>() / ratios.len() as f64),
+                    monthly,
+                    label,
+                }
+            })
+            .collect();
+        let most_neglected = label_stats
+            .iter()
+            .filter(|l| l.gap_ratio.is_some())
+            .max_by(|a, b| {
+                a.gap_ratio
+                    .unwrap_or_default()
+                    .total_cmp(&b.gap_ratio.unwrap_or_default())
+            })
+            .and_then(|l| l.label.id);
+
+        Stats {
+            months: months
+                .iter()
+                .map(|m| m.format("%Y-%m").to_string())
+                .collect(),
+            tasks: task_stats,
+            labels: label_stats,
+            most_neglected,
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use chrono::Local;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use diesel::dsl::{not, sql};
+use diesel::expression::SqlLiteral;
+use diesel::sql_types::{Nullable, Text};
 use diesel::{self, prelude::*};
 
 mod schema {
@@ -9,50 +12,343 @@ mod schema {
             name -> Text,
             description -> Text,
             updated_at -> Timestamp,
-            label_id -> Nullable<Integer>, // foreign key
+            interval_count -> Nullable<Integer>,
+            interval_unit -> Nullable<Text>,
+            household_id -> Integer, // foreign key
+            deleted_at -> Nullable<Timestamp>,
+            archived_at -> Nullable<Timestamp>,
         }
     }
 }
 
 use self::schema::tasks;
 
-use crate::DbConn;
+use crate::error::Error;
+use crate::models::audit::Activity;
+use crate::models::change;
+use crate::models::completion::Completion;
 use crate::models::label::Label;
+use crate::models::reminder::Reminder;
+use crate::models::schedule::{DueStatus, IntervalUnit, Period};
+use crate::models::task_label::{TaskLabel, task_labels};
+use crate::models::validation::FieldError;
+use crate::models::webhook::Event;
+use crate::{DbBackend, DbConn, DbConnection};
 
-#[derive(Associations, Identifiable, Serialize, Queryable, Insertable, Debug, Clone)]
+allow_tables_to_appear_in_same_query!(tasks, task_labels);
+
+#[derive(Identifiable, Serialize, Queryable, Insertable, Debug, Clone)]
 #[diesel(table_name = tasks)]
-#[diesel(belongs_to(Label, foreign_key = label_id))]
 pub struct Task {
     pub id: Option<i32>,
     pub name: String,
     pub description: String,
-    pub updated_at: String,
-    pub label_id: Option<i32>,
+    pub updated_at: NaiveDateTime,
+    /// How often the task should be done, e.g. every 2 `weeks`.
+    pub interval_count: Option<i32>,
+    pub interval_unit: Option<IntervalUnit>,
+    pub household_id: i32,
+    /// When the task was moved to the trash.
+    pub deleted_at: Option<NaiveDateTime>,
+    /// When the task was hidden from the index page.
+    pub archived_at: Option<NaiveDateTime>,
 }
 
-#[derive(FromForm)]
+/// A task together with its labels and when it should be done next.
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskSummary {
+    #[serde(flatten)]
+    pub task: Task,
+    pub label_ids: Vec<i32>,
+    pub next_due: Option<NaiveDate>,
+    pub status: Option<DueStatus>,
+}
+
+#[derive(FromForm, Deserialize)]
 pub struct TaskName {
     pub name: String,
 }
 
-#[derive(FromForm)]
+#[derive(FromForm, Deserialize)]
 pub struct TaskUpdate {
     pub name: String,
+    #[serde(default)]
     pub description: String,
+    /// A date like `2020-04-28`, or a date and time. Empty one keeps the
+    /// current date.
+    #[serde(default)]
     pub updated_at: String,
-    pub label_id: Option<i32>,
+    /// Ids of the labels the task should have. Unknown ids are ignored.
+    #[serde(default)]
+    pub label_ids: Vec<i32>,
+    /// Note attached to the completion recorded when `updated_at` changes.
+    pub note: Option<String>,
+    /// Moves the latest completion to `updated_at` instead of recording
+    /// another one, to fix a wrong date. Its note is kept unless `note`
+    /// replaces it.
+    #[serde(default)]
+    pub correct_latest: bool,
+    pub interval_count: Option<i32>,
+    pub interval_unit: Option<IntervalUnit>,
+}
+
+/// What to sort tasks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// The next due date. Tasks without interval follow, the oldest one
+    /// first.
+    #[default]
+    Due,
+    Name,
+    /// The last date the task was done.
+    Date,
+    /// The first label in alphabetical order. Tasks without labels follow.
+    Label,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Sorting and filtering of tasks, like
+/// `?sort=name&order=desc&label=1&unlabeled=true&older_than=30d`.
+///
+/// Fields with invalid values are ignored.
+#[derive(FromForm, Serialize, Debug, Clone, Default)]
+pub struct TaskQuery {
+    pub sort: Option<SortKey>,
+    pub order: Option<Order>,
+    /// Only tasks with this label.
+    pub label: Option<i32>,
+    /// Only tasks without labels.
+    pub unlabeled: bool,
+    /// Only tasks not done for longer than this.
+    pub older_than: Option<Period>,
+    /// Archived tasks instead of the others.
+    pub archived: bool,
+}
+
+/// Name of the first label of the task in alphabetical order.
+fn first_label_name() -> SqlLiteral<Nullable<Text>> {
+    sql("(SELECT MIN(labels.name) FROM task_labels \
+         JOIN labels ON labels.id = task_labels.label_id \
+         WHERE task_labels.task_id = tasks.id)")
+}
+
+impl TaskQuery {
+    /// Returns the query of the tasks of the household. Sorting by due date
+    /// is left to the caller, as the database doesn't know it.
+    fn build(&self, household_id: i32, today: NaiveDate) -> tasks::BoxedQuery<'static, DbBackend> {
+        let mut query = Task::in_household(household_id);
+        query = if self.archived {
+            query.filter(tasks::archived_at.is_not_null())
+        } else {
+            query.filter(tasks::archived_at.is_null())
+        };
+        if let Some(label_id) = self.label {
+            query = query.filter(
+                tasks::id.eq_any(
+                    task_labels::table
+                        .filter(task_labels::label_id.eq(label_id))
+                        .select(task_labels::task_id.nullable()),
+                ),
+            );
+        }
+        if self.unlabeled {
+            query = query.filter(not(
+                tasks::id.eq_any(task_labels::table.select(task_labels::task_id.nullable()))
+            ));
+        }
+        if let Some(cutoff) = self
+            .older_than
+            .and_then(|p| p.before(today))
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+        {
+            query = query.filter(tasks::updated_at.lt(cutoff));
+        }
+        let desc = self.order == Some(Order::Desc);
+        match (self.sort.unwrap_or_default(), desc) {
+            (SortKey::Name, false) => query.order((tasks::name.asc(), tasks::id.asc())),
+            (SortKey::Name, true) => query.order((tasks::name.desc(), tasks::id.desc())),
+            (SortKey::Due | SortKey::Date, false) => query.order(tasks::updated_at.asc()),
+            (SortKey::Date, true) => query.order(tasks::updated_at.desc()),
+            // Reversed after sorting by due date.
+            (SortKey::Due, true) => query.order(tasks::updated_at.asc()),
+            (SortKey::Label, false) => query.order((
+                first_label_name().is_null(),
+                first_label_name().asc(),
+                tasks::name.asc(),
+            )),
+            (SortKey::Label, true) => query.order((
+                first_label_name().is_null(),
+                first_label_name().desc(),
+                tasks::name.desc(),
+            )),
+        }
+    }
+}
+
+impl TaskName {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.is_empty() {
+            errors.push(FieldError::new("name", "Please input task name."));
+        }
+        errors
+    }
+}
+
+impl TaskUpdate {
+    /// Returns `updated_at` parsed, if any.
+    pub fn done_at(&self) -> Option<NaiveDateTime> {
+        let s = self.updated_at.trim();
+        ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)
+            })
+    }
+
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.is_empty() {
+            errors.push(FieldError::new("name", "Please input task name."));
+        }
+        if !self.updated_at.trim().is_empty() {
+            match self.done_at() {
+                None => errors.push(FieldError::new(
+                    "updated_at",
+                    "Please input last updated date like 2020-04-28.",
+                )),
+                Some(dt) if dt.date() > Local::now().date_naive() => errors.push(FieldError::new(
+                    "updated_at",
+                    "Last updated date can't be in the future.",
+                )),
+                Some(_) => {}
+            }
+        }
+        if self.interval_count.is_some() != self.interval_unit.is_some() {
+            errors.push(FieldError::new(
+                "interval_count",
+                "Please input both interval and its unit.",
+            ));
+        } else if self.interval_count.is_some_and(|n| n <= 0) {
+            errors.push(FieldError::new(
+                "interval_count",
+                "Please input positive interval.",
+            ));
+        }
+        errors
+    }
 }
 
 impl Task {
-    pub async fn all(conn: &DbConn) -> Vec<Task> {
+    /// Returns the date the task should be done next, if it has an interval.
+    pub fn next_due(&self) -> Option<NaiveDate> {
+        let count = u32::try_from(self.interval_count?).ok()?;
+        self.interval_unit?.add_to(self.updated_at.date(), count)
+    }
+
+    pub fn summary(self, label_ids: Vec<i32>, today: NaiveDate) -> TaskSummary {
+        let next_due = self.next_due();
+        TaskSummary {
+            status: next_due.map(|d| DueStatus::of(d, today)),
+            next_due,
+            label_ids,
+            task: self,
+        }
+    }
+
+    /// Attaches labels and due dates to the given tasks, keeping their order.
+    pub(crate) fn summarize(
+        tasks: Vec<Task>,
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<TaskSummary>> {
+        let today = Local::now().date_naive();
+        let ids: Vec<i32> = tasks.iter().filter_map(|t| t.id).collect();
+        let mut label_ids = TaskLabel::label_ids_of(&ids, c)?;
+        Ok(tasks
+            .into_iter()
+            .map(|t| {
+                let ids = t.id.and_then(|id| label_ids.remove(&id));
+                t.summary(ids.unwrap_or_default(), today)
+            })
+            .collect())
+    }
+
+    pub async fn all(household_id: i32, conn: &DbConn) -> Vec<Task> {
+        conn.run(move |c| Task::all_in(household_id, c).unwrap_or_default())
+            .await
+    }
+
+    /// Returns all tasks of the household including archived ones, with
+    /// their labels.
+    pub async fn all_summaries(household_id: i32, conn: &DbConn) -> Vec<TaskSummary> {
+        conn.run(move |c| {
+            let tasks = Task::all_in(household_id, c)?;
+            Task::summarize(tasks, c)
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Returns the query of the tasks of the household which aren't in the
+    /// trash.
+    fn in_household(household_id: i32) -> tasks::BoxedQuery<'static, DbBackend> {
+        tasks::table
+            .filter(tasks::household_id.eq(household_id))
+            .filter(tasks::deleted_at.is_null())
+            .into_boxed()
+    }
+
+    /// Returns all tasks of the household including archived ones.
+    pub(crate) fn all_in(household_id: i32, c: &mut DbConnection) -> QueryResult<Vec<Task>> {
         // Task hasn't been done for a long time should be in the top.
-        conn.run(|c| {
-            tasks::table
-                .order(tasks::updated_at.asc())
-                .load::<Task>(c)
-                .unwrap_or_default()
+        Task::in_household(household_id)
+            .order(tasks::updated_at.asc())
+            .load::<Task>(c)
+    }
+
+    /// Returns the tasks with the ids in no particular order.
+    pub(crate) fn by_ids(ids: &[i32], c: &mut DbConnection) -> QueryResult<Vec<Task>> {
+        tasks::table
+            .filter(tasks::id.eq_any(ids.iter().map(|&id| Some(id))))
+            .load::<Task>(c)
+    }
+
+    /// Returns all tasks, the most overdue one first.
+    ///
+    /// Tasks without interval follow, the oldest one first.
+    pub async fn all_by_due(household_id: i32, conn: &DbConn) -> Vec<TaskSummary> {
+        Task::list(household_id, TaskQuery::default(), conn).await
+    }
+
+    /// Returns tasks of the household sorted and filtered by the query.
+    pub async fn list(household_id: i32, query: TaskQuery, conn: &DbConn) -> Vec<TaskSummary> {
+        conn.run(move |c| {
+            let today = Local::now().date_naive();
+            let tasks = query.build(household_id, today).load::<Task>(c)?;
+            let mut tasks = Task::summarize(tasks, c)?;
+            if query.sort.unwrap_or_default() == SortKey::Due {
+                // `sort_by_key` is stable and keeps the order by date for
+                // tasks without interval.
+                tasks.sort_by_key(|t| (t.next_due.is_none(), t.next_due));
+                if query.order == Some(Order::Desc) {
+                    tasks.reverse();
+                }
+            }
+            Ok::<_, diesel::result::Error>(tasks)
         })
         .await
+        .unwrap_or_default()
     }
 
     #[cfg(test)]
@@ -63,56 +359,171 @@ impl Task {
             .await
     }
 
-    pub async fn task_by_id(id: i32, conn: &DbConn) -> Task {
+    /// Finds the task in the household, unless it's in the trash.
+    pub(crate) fn find(id: i32, household_id: i32, c: &mut DbConnection) -> QueryResult<Task> {
+        Task::in_household(household_id)
+            .filter(tasks::id.eq(id))
+            .first::<Task>(c)
+    }
+
+    /// Finds the task in the trash of the household.
+    fn find_deleted(id: i32, household_id: i32, c: &mut DbConnection) -> QueryResult<Task> {
+        tasks::table
+            .filter(tasks::id.eq(id))
+            .filter(tasks::household_id.eq(household_id))
+            .filter(tasks::deleted_at.is_not_null())
+            .first::<Task>(c)
+    }
+
+    pub async fn task_by_id(id: i32, household_id: i32, conn: &DbConn) -> Result<Task, Error> {
+        conn.run(move |c| Task::find(id, household_id, c))
+            .await
+            .map_err(Error::from)
+    }
+
+    pub async fn summary_by_id(
+        id: i32,
+        household_id: i32,
+        conn: &DbConn,
+    ) -> Result<TaskSummary, Error> {
         conn.run(move |c| {
-            tasks::table
-                .filter(tasks::id.eq(id))
-                .load::<Task>(c)
-                .unwrap()
-                .first()
-                .unwrap()
-                .clone()
+            let task = Task::find(id, household_id, c)?;
+            Task::summarize(vec![task], c)
         })
-        .await
+        .await?
+        .pop()
+        .ok_or(Error::NotFound)
     }
 
-    pub async fn tasks_by_label(label_id: i32, conn: &DbConn) -> Vec<Task> {
-        let label = Label::label_by_id(label_id, conn).await;
+    pub async fn tasks_by_label(
+        label_id: i32,
+        household_id: i32,
+        conn: &DbConn,
+    ) -> Result<Vec<TaskSummary>, Error> {
+        Label::label_by_id(label_id, household_id, conn).await?;
         conn.run(move |c| {
-            Task::belonging_to(&label)
+            let tasks = Task::in_household(household_id)
+                .filter(
+                    tasks::id.eq_any(
+                        task_labels::table
+                            .filter(task_labels::label_id.eq(label_id))
+                            .select(task_labels::task_id.nullable()),
+                    ),
+                )
                 .order(tasks::name)
-                .load::<Task>(c)
-                .unwrap()
+                .load::<Task>(c)?;
+            Task::summarize(tasks, c)
         })
         .await
+        .map_err(Error::from)
     }
 
-    pub async fn insert(task_name: TaskName, conn: &DbConn) -> bool {
-        let dt = Local::now().naive_local();
+    /// Returns the id of the new task. `actor` is the user who added it, if
+    /// known, for the activity log.
+    pub async fn insert(
+        task_name: TaskName,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Option<i32> {
+        Task::insert_with_labels(task_name, Vec::new(), household_id, actor, conn).await
+    }
+
+    /// Same as `insert`, with the labels with `label_ids` which exist in the
+    /// household.
+    pub async fn insert_with_labels(
+        task_name: TaskName,
+        label_ids: Vec<i32>,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Option<i32> {
+        conn.run(move |c| {
+            c.transaction(|c| Task::insert_in(task_name, &label_ids, household_id, actor, c))
+                .ok()
+        })
+        .await
+    }
+
+    /// Same as `insert_with_labels`, in the transaction of the caller.
+    pub(crate) fn insert_in(
+        task_name: TaskName,
+        label_ids: &[i32],
+        household_id: i32,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
         let t = Task {
             id: None,
             name: task_name.name,
             description: "".to_string(),
-            updated_at: dt.to_string(),
-            label_id: None,
+            updated_at: Local::now().naive_local(),
+            interval_count: None,
+            interval_unit: None,
+            household_id,
+            deleted_at: None,
+            archived_at: None,
         };
-        conn.run(move |c| {
-            diesel::insert_into(tasks::table)
-                .values(&t)
-                .execute(c)
-                .is_ok()
-        })
-        .await
+        let id = Task::create(&t, c)?;
+        Completion::record(id, t.updated_at, None, c)?;
+        let label_ids = Label::existing_ids(label_ids, household_id, c)?;
+        TaskLabel::set_for_task(id, &label_ids, c)?;
+        let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+        change::record(
+            household_id,
+            actor,
+            Event::TaskCreated,
+            id,
+            None,
+            Some(after),
+            c,
+        )?;
+        Ok(id)
+    }
+
+    /// Inserts the task as it is, without recording a completion. Returns
+    /// the id of the new task.
+    pub(crate) fn create(task: &Task, c: &mut DbConnection) -> QueryResult<i32> {
+        diesel::insert_into(tasks::table)
+            .values(task)
+            .returning(tasks::id)
+            .get_result::<Option<i32>>(c)
+            .map(|id| id.expect("inserted task has id"))
+    }
+
+    /// Returns the ones of the ids which are tasks of the household, in the
+    /// trash or not.
+    pub(crate) fn existing_ids(
+        ids: &[i32],
+        household_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<i32>> {
+        tasks::table
+            .filter(tasks::id.eq_any(ids.iter().map(|&id| Some(id))))
+            .filter(tasks::household_id.eq(household_id))
+            .select(tasks::id)
+            .load::<Option<i32>>(c)
+            .map(|ids| ids.into_iter().flatten().collect())
+    }
+
+    pub(crate) fn count(household_id: i32, c: &mut DbConnection) -> QueryResult<i64> {
+        Task::in_household(household_id).count().get_result(c)
     }
 
     #[cfg(test)]
-    pub async fn insert_with_old_date(dummy_name: &str, conn: &DbConn) -> bool {
+    pub async fn insert_with_old_date(dummy_name: &str, household_id: i32, conn: &DbConn) -> bool {
         let t = Task {
             id: None,
             name: dummy_name.to_string(),
             description: "".to_string(),
-            updated_at: "2000-01-01".to_string(),
-            label_id: None,
+            updated_at: NaiveDate::from_ymd_opt(2000, 1, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap(),
+            interval_count: None,
+            interval_unit: None,
+            household_id,
+            deleted_at: None,
+            archived_at: None,
         };
         conn.run(move |c| {
             diesel::insert_into(tasks::table)
@@ -123,44 +534,343 @@ impl Task {
         .await
     }
 
-    pub async fn update(id: i32, task: TaskUpdate, conn: &DbConn) -> bool {
+    pub async fn update(
+        id: i32,
+        household_id: i32,
+        task: TaskUpdate,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> bool {
         conn.run(move |c| {
-            diesel::update(tasks::table.filter(tasks::id.eq(id)))
-                .set((
-                    tasks::name.eq(task.name),
-                    tasks::description.eq(task.description),
-                    tasks::updated_at.eq(task.updated_at),
-                    tasks::label_id.eq(task.label_id),
-                ))
-                .execute(c)
+            c.transaction(|c| Task::update_in(id, household_id, task, actor, c))
                 .is_ok()
         })
         .await
     }
 
-    pub async fn update_to_today(id: i32, conn: &DbConn) -> bool {
+    /// Same as `update`, in the transaction of the caller.
+    pub(crate) fn update_in(
+        id: i32,
+        household_id: i32,
+        task: TaskUpdate,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<()> {
+        let done_at = task.done_at();
+        let before = Task::find(id, household_id, c)?;
+        let current = before.updated_at;
+        let before = Activity::task_state(&before, c)?;
+        diesel::update(tasks::table.filter(tasks::id.eq(id)))
+            .set((
+                tasks::name.eq(task.name),
+                tasks::description.eq(task.description),
+                tasks::interval_count.eq(task.interval_count),
+                tasks::interval_unit.eq(task.interval_unit),
+            ))
+            .execute(c)?;
+        let label_ids = Label::existing_ids(&task.label_ids, household_id, c)?;
+        TaskLabel::set_for_task(id, &label_ids, c)?;
+        // Another date means the task was also done on that date, so an
+        // earlier one only adds to the history, unless it corrects the
+        // latest completion.
+        if let Some(done_at) = done_at.filter(|d| d.date() != current.date()) {
+            let note = task.note.filter(|n| !n.is_empty());
+            if task.correct_latest && Completion::move_latest(id, done_at, note.clone(), c)? > 0 {
+                Task::refresh_updated_at(id, c)?;
+            } else {
+                Task::complete(id, done_at, note, c)?;
+            }
+        }
+        let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+        change::record(
+            household_id,
+            actor,
+            Event::TaskUpdated,
+            id,
+            Some(before),
+            Some(after),
+            c,
+        )?;
+        Ok(())
+    }
+
+    pub async fn update_to_today(
+        id: i32,
+        household_id: i32,
+        note: Option<String>,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> bool {
+        Task::complete_now(id, household_id, note, actor, conn)
+            .await
+            .is_ok()
+    }
+
+    /// Records that the task was done now. Returns the id of the completion
+    /// and the previous `updated_at`.
+    pub async fn complete_now(
+        id: i32,
+        household_id: i32,
+        note: Option<String>,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Result<(i32, NaiveDateTime), Error> {
         let dt = Local::now().naive_local();
         conn.run(move |c| {
-            diesel::update(tasks::table.filter(tasks::id.eq(id)))
-                .set(tasks::updated_at.eq(dt.to_string()))
-                .execute(c)
-                .is_ok()
+            c.transaction(|c| {
+                let before = Task::find(id, household_id, c)?;
+                let previous = before.updated_at;
+                let before = Activity::task_state(&before, c)?;
+                let completion_id = Task::complete(id, dt, note, c)?;
+                let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+                change::record(
+                    household_id,
+                    actor,
+                    Event::TaskCompleted,
+                    id,
+                    Some(before),
+                    Some(after),
+                    c,
+                )?;
+                Ok::<_, diesel::result::Error>((completion_id, previous))
+            })
+        })
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Records a completion and refreshes `updated_at` to the latest one.
+    /// Returns the id of the completion.
+    fn complete(
+        id: i32,
+        completed_at: NaiveDateTime,
+        note: Option<String>,
+        c: &mut DbConnection,
+    ) -> QueryResult<i32> {
+        let completion_id = Completion::record(id, completed_at, note, c)?;
+        Task::refresh_updated_at(id, c)?;
+        Ok(completion_id)
+    }
+
+    /// Deletes a completion of the task in the household, and sets
+    /// `updated_at` back to the latest one left, or to `previous` without
+    /// any.
+    pub(crate) fn uncomplete(
+        id: i32,
+        completion_id: i32,
+        previous: NaiveDateTime,
+        household_id: i32,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let before = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+        if Completion::delete_with_id(completion_id, id, c)? == 0 {
+            return Err(diesel::result::Error::NotFound);
+        }
+        let latest = Completion::latest(id, c)?.unwrap_or(previous);
+        diesel::update(tasks::table.filter(tasks::id.eq(id)))
+            .set(tasks::updated_at.eq(latest))
+            .execute(c)?;
+        let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+        change::record(
+            household_id,
+            actor,
+            Event::TaskUpdated,
+            id,
+            Some(before),
+            Some(after),
+            c,
+        )
+    }
+
+    fn refresh_updated_at(id: i32, c: &mut DbConnection) -> QueryResult<usize> {
+        match Completion::latest(id, c)? {
+            Some(latest) => diesel::update(tasks::table.filter(tasks::id.eq(id)))
+                .set(tasks::updated_at.eq(latest))
+                .execute(c),
+            None => Ok(0),
+        }
+    }
+
+    /// Hides the task from the index page, or shows it again.
+    pub async fn set_archived(
+        id: i32,
+        household_id: i32,
+        archived: bool,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Result<(), Error> {
+        let archived_at = archived.then(|| Local::now().naive_local());
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let before = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+                diesel::update(tasks::table.filter(tasks::id.eq(id)))
+                    .set(tasks::archived_at.eq(archived_at))
+                    .execute(c)?;
+                let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+                change::record(
+                    household_id,
+                    actor,
+                    Event::TaskUpdated,
+                    id,
+                    Some(before),
+                    Some(after),
+                    c,
+                )
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Moves the task to the trash.
+    pub async fn delete_with_id(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> bool {
+        Task::move_to_trash(id, household_id, actor, conn)
+            .await
+            .is_ok()
+    }
+
+    /// Moves the task to the trash. Fails with `Error::NotFound` if the
+    /// household has no such task.
+    pub async fn move_to_trash(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Result<(), Error> {
+        let now = Local::now().naive_local();
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let before = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+                diesel::update(tasks::table.filter(tasks::id.eq(id)))
+                    .set(tasks::deleted_at.eq(now))
+                    .execute(c)?;
+                change::record(
+                    household_id,
+                    actor,
+                    Event::TaskDeleted,
+                    id,
+                    Some(before),
+                    None,
+                    c,
+                )
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Returns tasks in the trash, the latest deleted one first.
+    pub async fn trash(household_id: i32, conn: &DbConn) -> Vec<Task> {
+        conn.run(move |c| {
+            tasks::table
+                .filter(tasks::household_id.eq(household_id))
+                .filter(tasks::deleted_at.is_not_null())
+                .order(tasks::deleted_at.desc())
+                .load::<Task>(c)
+                .unwrap_or_default()
         })
         .await
     }
 
-    pub async fn delete_with_id(id: i32, conn: &DbConn) -> bool {
+    /// Takes the task out of the trash.
+    pub async fn restore(id: i32, household_id: i32, actor: Option<i32>, conn: &DbConn) -> bool {
         conn.run(move |c| {
-            diesel::delete(tasks::table.filter(tasks::id.eq(id)))
-                .execute(c)
+            c.transaction(|c| Task::undelete(id, household_id, actor, c))
                 .is_ok()
         })
         .await
     }
 
+    /// Takes the task out of the trash. It's logged as created, since it
+    /// was logged as deleted when it went there.
+    pub(crate) fn undelete(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        Task::find_deleted(id, household_id, c)?;
+        diesel::update(tasks::table.filter(tasks::id.eq(id)))
+            .set(tasks::deleted_at.eq(None::<NaiveDateTime>))
+            .execute(c)?;
+        let after = Activity::task_state(&Task::find(id, household_id, c)?, c)?;
+        change::record(
+            household_id,
+            actor,
+            Event::TaskCreated,
+            id,
+            None,
+            Some(after),
+            c,
+        )
+    }
+
+    /// Deletes the task in the trash with its history for good.
+    pub async fn purge(id: i32, household_id: i32, actor: Option<i32>, conn: &DbConn) -> bool {
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let task = Task::find_deleted(id, household_id, c)?;
+                Task::destroy(&task, actor, c)
+            })
+            .is_ok()
+        })
+        .await
+    }
+
+    /// Purges tasks of any household which were moved to the trash before
+    /// `deleted_before`. Returns the number of tasks purged.
+    pub(crate) fn purge_deleted_before(
+        deleted_before: NaiveDateTime,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        c.transaction(|c| {
+            let tasks = tasks::table
+                .filter(tasks::deleted_at.lt(deleted_before))
+                .load::<Task>(c)?;
+            for task in &tasks {
+                Task::destroy(task, None, c)?;
+            }
+            Ok(tasks.len())
+        })
+    }
+
+    /// Deletes the task with its history, and logs it as purged by `actor`,
+    /// or by nobody when the trash is emptied on its own.
+    fn destroy(task: &Task, actor: Option<i32>, c: &mut DbConnection) -> QueryResult<usize> {
+        let id = task.id.expect("stored task has id");
+        let before = Activity::task_state(task, c)?;
+        Completion::delete_for_task(id, c)?;
+        TaskLabel::delete_for_task(id, c)?;
+        Reminder::delete_for_task(id, c)?;
+        diesel::delete(tasks::table.filter(tasks::id.eq(id))).execute(c)?;
+        change::record(
+            task.household_id,
+            actor,
+            Event::TaskPurged,
+            id,
+            Some(before),
+            None,
+            c,
+        )
+    }
+
     #[cfg(test)]
     pub async fn delete_all(conn: &DbConn) -> bool {
-        conn.run(|c| diesel::delete(tasks::table).execute(c).is_ok())
-            .await
+        conn.run(|c| {
+            c.transaction(|c| {
+                Completion::delete_all(c)?;
+                TaskLabel::delete_all(c)?;
+                Reminder::delete_all(c)?;
+                diesel::delete(tasks::table).execute(c)
+            })
+            .is_ok()
+        })
+        .await
     }
 }
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use diesel::{self, prelude::*};
+
+mod schema {
+    table! {
+        task_labels (task_id, label_id) {
+            task_id -> Integer, // foreign key
+            label_id -> Integer, // foreign key
+        }
+    }
+}
+
+pub(crate) use self::schema::task_labels;
+
+use crate::DbConnection;
+
+/// Which labels a task has. A task can have several labels.
+#[derive(Insertable, Queryable, Debug, Clone, Copy)]
+#[diesel(table_name = task_labels)]
+pub struct TaskLabel {
+    pub task_id: i32,
+    pub label_id: i32,
+}
+
+impl TaskLabel {
+    /// Returns label ids of each of the given tasks.
+    pub(crate) fn label_ids_of(
+        task_ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<HashMap<i32, Vec<i32>>> {
+        let pairs = task_labels::table
+            .filter(task_labels::task_id.eq_any(task_ids))
+            .order(task_labels::label_id)
+            .load::<TaskLabel>(c)?;
+        let mut label_ids: HashMap<i32, Vec<i32>> = HashMap::new();
+        for p in pairs {
+            label_ids.entry(p.task_id).or_default().push(p.label_id);
+        }
+        Ok(label_ids)
+    }
+
+    /// Replaces labels of the task with the given ones.
+    pub(crate) fn set_for_task(
+        task_id: i32,
+        label_ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        TaskLabel::delete_for_task(task_id, c)?;
+        let mut rows: Vec<TaskLabel> = label_ids
+            .iter()
+            .map(|&label_id| TaskLabel { task_id, label_id })
+            .collect();
+        rows.sort_by_key(|r| r.label_id);
+        rows.dedup_by_key(|r| r.label_id);
+        diesel::insert_into(task_labels::table)
+            .values(&rows)
+            .execute(c)
+    }
+
+    /// Returns ids of the tasks with the label.
+    pub(crate) fn task_ids_of(label_id: i32, c: &mut DbConnection) -> QueryResult<Vec<i32>> {
+        task_labels::table
+            .filter(task_labels::label_id.eq(label_id))
+            .select(task_labels::task_id)
+            .order(task_labels::task_id)
+            .load::<i32>(c)
+    }
+
+    /// Puts the label on the given tasks.
+    pub(crate) fn add_to_tasks(
+        label_id: i32,
+        task_ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let rows: Vec<TaskLabel> = task_ids
+            .iter()
+            .map(|&task_id| TaskLabel { task_id, label_id })
+            .collect();
+        diesel::insert_into(task_labels::table)
+            .values(&rows)
+            .execute(c)
+    }
+
+    pub(crate) fn delete_for_task(task_id: i32, c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_labels::table.filter(task_labels::task_id.eq(task_id))).execute(c)
+    }
+
+    pub(crate) fn delete_for_label(label_id: i32, c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_labels::table.filter(task_labels::label_id.eq(label_id))).execute(c)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn delete_all(c: &mut DbConnection) -> QueryResult<usize> {
+        diesel::delete(task_labels::table).execute(c)
+    }
+}
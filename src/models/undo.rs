@@ -0,0 +1,168 @@
+//! Undoing "I did it today!" and deletions for a while after them.
+
+use chrono::{Duration, Local, NaiveDateTime};
+use diesel::{self, prelude::*};
+
+mod schema {
+    table! {
+        undo_actions {
+            id -> Integer,
+            household_id -> Integer, // foreign key
+            action -> Text,
+            created_at -> Timestamp,
+        }
+    }
+}
+
+use self::schema::undo_actions;
+
+use crate::DbConn;
+use crate::models::label::Label;
+use crate::models::task::Task;
+
+/// How long a change can be undone.
+pub const UNDO_WINDOW_MINUTES: i64 = 10;
+
+/// What undoes a change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Undo {
+    /// Forgets the completion recorded by "I did it today!".
+    Completion {
+        task_id: i32,
+        completion_id: i32,
+        /// `updated_at` before the completion.
+        previous: NaiveDateTime,
+    },
+    /// Takes the deleted task out of the trash.
+    TaskDeletion { task_id: i32 },
+    /// Puts the deleted label back on its tasks.
+    LabelDeletion {
+        id: i32,
+        name: String,
+        color_hex: String,
+        task_ids: Vec<i32>,
+    },
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = undo_actions)]
+struct NewUndo {
+    household_id: i32,
+    action: String,
+    created_at: NaiveDateTime,
+}
+
+impl Undo {
+    pub fn label_deletion(label: Label, task_ids: Vec<i32>) -> Option<Undo> {
+        Some(Undo::LabelDeletion {
+            id: label.id?,
+            name: label.name,
+            color_hex: label.color_hex,
+            task_ids,
+        })
+    }
+
+    /// Where the change was made.
+    pub fn page(&self) -> &'static str {
+        match self {
+            Undo::Completion { .. } | Undo::TaskDeletion { .. } => "/",
+            Undo::LabelDeletion { .. } => "/label",
+        }
+    }
+
+    /// Keeps the undo for `UNDO_WINDOW_MINUTES`. Returns its id.
+    pub async fn save(self, household_id: i32, conn: &DbConn) -> Option<i32> {
+        let now = Local::now().naive_local();
+        let undo = NewUndo {
+            household_id,
+            action: serde_json::to_string(&self).ok()?,
+            created_at: now,
+        };
+        conn.run(move |c| {
+            // Expired ones are no use any more.
+            diesel::delete(
+                undo_actions::table.filter(
+                    undo_actions::created_at.lt(now - Duration::minutes(UNDO_WINDOW_MINUTES)),
+                ),
+            )
+            .execute(c)
+            .ok()?;
+            diesel::insert_into(undo_actions::table)
+                .values(&undo)
+                .returning(undo_actions::id)
+                .get_result::<i32>(c)
+                .ok()
+        })
+        .await
+    }
+
+    /// Undoes the change, once and only within `UNDO_WINDOW_MINUTES`.
+    /// Returns what was undone. It's logged as a change made by `actor`.
+    pub async fn apply(
+        id: i32,
+        household_id: i32,
+        actor: Option<i32>,
+        conn: &DbConn,
+    ) -> Option<Undo> {
+        let since = Local::now().naive_local() - Duration::minutes(UNDO_WINDOW_MINUTES);
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let query = undo_actions::table
+                    .filter(undo_actions::id.eq(id))
+                    .filter(undo_actions::household_id.eq(household_id))
+                    .filter(undo_actions::created_at.ge(since));
+                let action = query.select(undo_actions::action).first::<String>(c)?;
+                diesel::delete(query).execute(c)?;
+                let undo: Undo = serde_json::from_str(&action)
+                    .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+                match &undo {
+                    Undo::Completion {
+                        task_id,
+                        completion_id,
+                        previous,
+                    } => Task::uncomplete(
+                        *task_id,
+                        *completion_id,
+                        *previous,
+                        household_id,
+                        actor,
+                        c,
+                    )?,
+                    Undo::TaskDeletion { task_id } => {
+                        Task::undelete(*task_id, household_id, actor, c)?
+                    }
+                    Undo::LabelDeletion {
+                        id,
+                        name,
+                        color_hex,
+                        task_ids,
+                    } => {
+                        let label = Label {
+                            id: Some(*id),
+                            name: name.clone(),
+                            color_hex: color_hex.clone(),
+                            household_id,
+                        };
+                        Label::put_back(&label, task_ids, actor, c)?
+                    }
+                };
+                Ok::<_, diesel::result::Error>(undo)
+            })
+            .ok()
+        })
+        .await
+    }
+
+    #[cfg(test)]
+    pub async fn expire_all(conn: &DbConn) -> bool {
+        let long_ago = Local::now().naive_local() - Duration::days(1);
+        conn.run(move |c| {
+            diesel::update(undo_actions::table)
+                .set(undo_actions::created_at.eq(long_ago))
+                .execute(c)
+                .is_ok()
+        })
+        .await
+    }
+}
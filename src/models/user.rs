@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use argon2::Argon2;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use diesel::{self, prelude::*};
+
+mod schema {
+    table! {
+        users {
+            id -> Integer,
+            name -> Text,
+            password_hash -> Text,
+            email -> Nullable<Text>,
+        }
+    }
+}
+
+use self::schema::users;
+
+use crate::error::Error;
+use crate::models::household::Household;
+use crate::models::validation::FieldError;
+use crate::{DbConn, DbConnection};
+
+/// Passwords shorter than this are rejected.
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Identifiable, Serialize, Queryable, Debug, Clone)]
+#[diesel(table_name = users)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    /// Where reminders are sent.
+    pub email: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = users)]
+struct NewUser {
+    name: String,
+    password_hash: String,
+}
+
+#[derive(FromForm, Deserialize)]
+pub struct Credentials {
+    pub name: String,
+    pub password: String,
+}
+
+#[derive(FromForm, Deserialize)]
+pub struct EmailForm {
+    /// Empty one stops reminders.
+    pub email: String,
+}
+
+impl EmailForm {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let email = self.email.trim();
+        if !email.is_empty() && email.parse::<lettre::Address>().is_err() {
+            errors.push(FieldError::new(
+                "email",
+                "Please input email address like you@example.com.",
+            ));
+        }
+        errors
+    }
+}
+
+impl Credentials {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "Please input user name."));
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LEN {
+            errors.push(FieldError::new(
+                "password",
+                "Please input password of at least 8 characters.",
+            ));
+        }
+        errors
+    }
+}
+
+fn hash_password(password: &str) -> Option<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .ok()
+}
+
+impl User {
+    fn verify_password(&self, password: &str) -> bool {
+        PasswordHash::new(&self.password_hash).is_ok_and(|h| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &h)
+                .is_ok()
+        })
+    }
+
+    pub async fn count(conn: &DbConn) -> i64 {
+        conn.run(|c| users::table.count().get_result(c).unwrap_or_default())
+            .await
+    }
+
+    pub async fn user_by_id(id: i32, conn: &DbConn) -> Result<User, Error> {
+        conn.run(move |c| users::table.filter(users::id.eq(id)).first::<User>(c))
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Returns the user if the name and password match.
+    pub async fn authenticate(credentials: Credentials, conn: &DbConn) -> Option<User> {
+        conn.run(move |c| {
+            users::table
+                .filter(users::name.eq(credentials.name.trim()))
+                .first::<User>(c)
+                .ok()
+                .filter(|u| u.verify_password(&credentials.password))
+        })
+        .await
+    }
+
+    /// Returns names of the given users, sorted.
+    pub(crate) fn names_of(ids: &[i32], c: &mut DbConnection) -> QueryResult<Vec<String>> {
+        users::table
+            .filter(users::id.eq_any(ids))
+            .select(users::name)
+            .order(users::name)
+            .load::<String>(c)
+    }
+
+    /// Returns names of the given users by their ids.
+    pub(crate) fn names_by_id(
+        ids: &[i32],
+        c: &mut DbConnection,
+    ) -> QueryResult<HashMap<i32, String>> {
+        users::table
+            .filter(users::id.eq_any(ids))
+            .select((users::id, users::name))
+            .load::<(i32, String)>(c)
+            .map(|pairs| pairs.into_iter().collect())
+    }
+
+    /// Returns email addresses of the given users who have one.
+    pub(crate) fn emails_of(ids: &[i32], c: &mut DbConnection) -> QueryResult<Vec<String>> {
+        users::table
+            .filter(users::id.eq_any(ids))
+            .select(users::email)
+            .order(users::id)
+            .load::<Option<String>>(c)
+            .map(|emails| emails.into_iter().flatten().collect())
+    }
+
+    pub async fn set_email(id: i32, form: EmailForm, conn: &DbConn) -> bool {
+        let email = Some(form.email.trim().to_string()).filter(|e| !e.is_empty());
+        conn.run(move |c| {
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set(users::email.eq(email))
+                .execute(c)
+                .is_ok_and(|n| n > 0)
+        })
+        .await
+    }
+
+    pub(crate) fn id_by_name(name: &str, c: &mut DbConnection) -> QueryResult<Option<i32>> {
+        users::table
+            .filter(users::name.eq(name.trim()))
+            .select(users::id)
+            .first::<i32>(c)
+            .optional()
+    }
+
+    /// Returns the id of the new user, or `None` if the name is taken.
+    ///
+    /// The user also gets a new household of their own.
+    pub async fn insert(credentials: Credentials, conn: &DbConn) -> Option<i32> {
+        conn.run(move |c| {
+            let u = NewUser {
+                name: credentials.name.trim().to_string(),
+                password_hash: hash_password(&credentials.password)?,
+            };
+            c.transaction(|c| {
+                let id = diesel::insert_into(users::table)
+                    .values(&u)
+                    .returning(users::id)
+                    .get_result::<i32>(c)?;
+                Household::create_for(id, &u.name, c)?;
+                Ok::<_, diesel::result::Error>(id)
+            })
+            .ok()
+        })
+        .await
+    }
+
+    #[cfg(test)]
+    pub async fn delete_all(conn: &DbConn) -> bool {
+        conn.run(|c| diesel::delete(users::table).execute(c).is_ok())
+            .await
+    }
+}
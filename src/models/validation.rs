@@ -0,0 +1,12 @@
+/// A user input which can't be stored, with the form field it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: &'static str) -> FieldError {
+        FieldError { field, message }
+    }
+}
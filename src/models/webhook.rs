@@ -0,0 +1,429 @@
+use std::fmt;
+
+use chrono::{Local, NaiveDateTime, Utc};
+use diesel::{self, prelude::*};
+use serde_json::{Value, json};
+
+mod schema {
+    table! {
+        webhooks {
+            id -> Integer,
+            household_id -> Integer, // foreign key
+            url -> Text,
+            secret -> Text,
+            events -> Text,
+        }
+    }
+
+    table! {
+        webhook_deliveries {
+            id -> Integer,
+            webhook_id -> Integer, // foreign key
+            event -> Text,
+            attempt -> Integer,
+            status_code -> Nullable<Integer>,
+            error -> Nullable<Text>,
+            attempted_at -> Timestamp,
+        }
+    }
+
+    table! {
+        webhook_queue {
+            id -> Integer,
+            webhook_id -> Integer, // foreign key
+            event -> Text,
+            body -> Text,
+            attempts -> Integer,
+            next_attempt_at -> Timestamp,
+        }
+    }
+
+    joinable!(webhook_deliveries -> webhooks (webhook_id));
+    joinable!(webhook_queue -> webhooks (webhook_id));
+    allow_tables_to_appear_in_same_query!(webhooks, webhook_deliveries, webhook_queue);
+}
+
+use self::schema::{webhook_deliveries, webhook_queue, webhooks};
+
+use crate::models::label::Label;
+use crate::models::task::Task;
+use crate::models::validation::FieldError;
+use crate::{DbConn, DbConnection};
+
+/// Things which happen to tasks and labels, sent to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, FromFormField)]
+pub enum Event {
+    #[serde(rename = "task.created")]
+    #[field(value = "task.created")]
+    TaskCreated,
+    #[serde(rename = "task.updated")]
+    #[field(value = "task.updated")]
+    TaskUpdated,
+    /// "I did it today!"
+    #[serde(rename = "task.completed")]
+    #[field(value = "task.completed")]
+    TaskCompleted,
+    /// Moved to the trash.
+    #[serde(rename = "task.deleted")]
+    #[field(value = "task.deleted")]
+    TaskDeleted,
+    /// Deleted from the trash for good.
+    #[serde(rename = "task.purged")]
+    #[field(value = "task.purged")]
+    TaskPurged,
+    #[serde(rename = "label.created")]
+    #[field(value = "label.created")]
+    LabelCreated,
+    #[serde(rename = "label.updated")]
+    #[field(value = "label.updated")]
+    LabelUpdated,
+    #[serde(rename = "label.deleted")]
+    #[field(value = "label.deleted")]
+    LabelDeleted,
+}
+
+impl Event {
+    pub const ALL: [Event; 8] = [
+        Event::TaskCreated,
+        Event::TaskUpdated,
+        Event::TaskCompleted,
+        Event::TaskDeleted,
+        Event::TaskPurged,
+        Event::LabelCreated,
+        Event::LabelUpdated,
+        Event::LabelDeleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::TaskCreated => "task.created",
+            Event::TaskUpdated => "task.updated",
+            Event::TaskCompleted => "task.completed",
+            Event::TaskDeleted => "task.deleted",
+            Event::TaskPurged => "task.purged",
+            Event::LabelCreated => "label.created",
+            Event::LabelUpdated => "label.updated",
+            Event::LabelDeleted => "label.deleted",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A URL which is sent the events it subscribes to, signed with `secret`.
+#[derive(Serialize, Queryable, Debug, Clone)]
+pub struct Webhook {
+    pub id: i32,
+    pub household_id: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Comma separated event names.
+    pub events: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhooks)]
+struct NewWebhook {
+    household_id: i32,
+    url: String,
+    secret: String,
+    events: String,
+}
+
+#[derive(FromForm)]
+pub struct WebhookForm {
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<Event>,
+}
+
+/// One attempt to send an event to a webhook.
+#[derive(Serialize, Queryable, Debug, Clone)]
+pub struct Delivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    /// 1 for the first attempt, 2 for the first retry and so on.
+    pub attempt: i32,
+    /// Response status, if the webhook answered.
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhook_deliveries)]
+struct NewDelivery<'a> {
+    webhook_id: i32,
+    event: &'a str,
+    attempt: i32,
+    status_code: Option<i32>,
+    error: Option<&'a str>,
+    attempted_at: NaiveDateTime,
+}
+
+/// An event waiting to be sent to a webhook.
+#[derive(Queryable, Debug, Clone)]
+pub struct Job {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub body: String,
+    /// Attempts made so far.
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhook_queue)]
+struct NewJob<'a> {
+    webhook_id: i32,
+    event: &'a str,
+    body: &'a str,
+    next_attempt_at: NaiveDateTime,
+}
+
+/// Number of deliveries shown in the log.
+const RECENT_DELIVERIES: i64 = 50;
+
+impl WebhookForm {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            errors.push(FieldError::new(
+                "url",
+                "Please input URL starting with http:// or https://.",
+            ));
+        }
+        if self.secret.is_empty() {
+            errors.push(FieldError::new("secret", "Please input secret."));
+        }
+        if self.events.is_empty() {
+            errors.push(FieldError::new("events", "Please choose events to send."));
+        }
+        errors
+    }
+}
+
+/// `data` of events: the task or label as it is now, or only its
+/// id once it's gone.
+fn data(
+    event: Event,
+    entity_id: i32,
+    household_id: i32,
+    c: &mut DbConnection,
+) -> QueryResult<Value> {
+    let data = match event {
+        Event::TaskDeleted | Event::TaskPurged | Event::LabelDeleted => json!({ "id": entity_id }),
+        Event::LabelCreated | Event::LabelUpdated => {
+            json!(Label::find(entity_id, household_id, c)?)
+        }
+        Event::TaskCreated | Event::TaskUpdated | Event::TaskCompleted => {
+            let task = Task::find(entity_id, household_id, c)?;
+            json!(Task::summarize(vec![task], c)?.pop())
+        }
+    };
+    Ok(data)
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: Event) -> bool {
+        self.events.split(',').any(|e| e == event.as_str())
+    }
+
+    pub async fn all(household_id: i32, conn: &DbConn) -> Vec<Webhook> {
+        conn.run(move |c| {
+            webhooks::table
+                .filter(webhooks::household_id.eq(household_id))
+                .order(webhooks::id)
+                .load::<Webhook>(c)
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    /// Returns webhooks of the household subscribing to the event.
+    pub(crate) fn subscribed(
+        household_id: i32,
+        event: Event,
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<Webhook>> {
+        let all = webhooks::table
+            .filter(webhooks::household_id.eq(household_id))
+            .load::<Webhook>(c)?;
+        Ok(all.into_iter().filter(|w| w.subscribes_to(event)).collect())
+    }
+
+    /// Queues the event of the task or label `entity_id` for the webhooks
+    /// of the household subscribing to it, if any.
+    pub(crate) fn enqueue(
+        household_id: i32,
+        event: Event,
+        entity_id: i32,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let webhooks = Webhook::subscribed(household_id, event, c)?;
+        if webhooks.is_empty() {
+            return Ok(0);
+        }
+        let body = json!({
+            "event": event,
+            "household_id": household_id,
+            "occurred_at": Utc::now().to_rfc3339(),
+            "data": data(event, entity_id, household_id, c)?,
+        })
+        .to_string();
+        let jobs: Vec<NewJob> = webhooks
+            .iter()
+            .map(|w| NewJob {
+                webhook_id: w.id,
+                event: event.as_str(),
+                body: &body,
+                next_attempt_at: Local::now().naive_local(),
+            })
+            .collect();
+        diesel::insert_into(webhook_queue::table)
+            .values(&jobs)
+            .execute(c)
+    }
+
+    pub async fn insert(form: WebhookForm, household_id: i32, conn: &DbConn) -> bool {
+        let events: Vec<&str> = Event::ALL
+            .iter()
+            .filter(|e| form.events.contains(e))
+            .map(Event::as_str)
+            .collect();
+        let webhook = NewWebhook {
+            household_id,
+            url: form.url,
+            secret: form.secret,
+            events: events.join(","),
+        };
+        conn.run(move |c| {
+            diesel::insert_into(webhooks::table)
+                .values(&webhook)
+                .execute(c)
+                .is_ok()
+        })
+        .await
+    }
+
+    pub async fn delete_with_id(id: i32, household_id: i32, conn: &DbConn) -> bool {
+        conn.run(move |c| {
+            c.transaction(|c| {
+                let webhook = webhooks::table
+                    .filter(webhooks::id.eq(id))
+                    .filter(webhooks::household_id.eq(household_id));
+                webhook.select(webhooks::id).first::<i32>(c)?;
+                diesel::delete(
+                    webhook_deliveries::table.filter(webhook_deliveries::webhook_id.eq(id)),
+                )
+                .execute(c)?;
+                diesel::delete(webhook_queue::table.filter(webhook_queue::webhook_id.eq(id)))
+                    .execute(c)?;
+                diesel::delete(webhook).execute(c)
+            })
+            .is_ok()
+        })
+        .await
+    }
+
+    #[cfg(test)]
+    pub async fn delete_all(conn: &DbConn) -> bool {
+        conn.run(|c| {
+            c.transaction(|c| {
+                diesel::delete(webhook_deliveries::table).execute(c)?;
+                diesel::delete(webhook_queue::table).execute(c)?;
+                diesel::delete(webhooks::table).execute(c)
+            })
+            .is_ok()
+        })
+        .await
+    }
+}
+
+impl Delivery {
+    /// Returns the latest deliveries to webhooks of the household.
+    pub async fn recent(household_id: i32, conn: &DbConn) -> Vec<Delivery> {
+        conn.run(move |c| {
+            webhook_deliveries::table
+                .inner_join(webhooks::table)
+                .filter(webhooks::household_id.eq(household_id))
+                .select(webhook_deliveries::all_columns)
+                .order(webhook_deliveries::id.desc())
+                .limit(RECENT_DELIVERIES)
+                .load::<Delivery>(c)
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    pub(crate) fn record(
+        webhook_id: i32,
+        event: &str,
+        attempt: i32,
+        result: &Result<u16, String>,
+        attempted_at: NaiveDateTime,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let delivery = NewDelivery {
+            webhook_id,
+            event,
+            attempt,
+            status_code: result.as_ref().ok().map(|&s| i32::from(s)),
+            error: result.as_ref().err().map(String::as_str),
+            attempted_at,
+        };
+        diesel::insert_into(webhook_deliveries::table)
+            .values(&delivery)
+            .execute(c)
+    }
+}
+
+impl Job {
+    /// Returns the jobs due at `now` with their webhooks, and puts them off
+    /// until `claimed_until`, so that they aren't taken again while they
+    /// are being sent. A job left so by a crash is retried then.
+    pub(crate) fn claim_due(
+        now: NaiveDateTime,
+        claimed_until: NaiveDateTime,
+        c: &mut DbConnection,
+    ) -> QueryResult<Vec<(Job, Webhook)>> {
+        c.transaction(|c| {
+            let due = webhook_queue::table
+                .inner_join(webhooks::table)
+                .filter(webhook_queue::next_attempt_at.le(now))
+                .order(webhook_queue::id)
+                .load::<(Job, Webhook)>(c)?;
+            let ids: Vec<i32> = due.iter().map(|(job, _)| job.id).collect();
+            diesel::update(webhook_queue::table.filter(webhook_queue::id.eq_any(&ids)))
+                .set(webhook_queue::next_attempt_at.eq(claimed_until))
+                .execute(c)?;
+            Ok(due)
+        })
+    }
+
+    /// Counts an attempt of the job, and schedules the next one at
+    /// `retry_at`, or forgets the job without it.
+    pub(crate) fn attempted(
+        id: i32,
+        retry_at: Option<NaiveDateTime>,
+        c: &mut DbConnection,
+    ) -> QueryResult<usize> {
+        let job = webhook_queue::table.filter(webhook_queue::id.eq(id));
+        match retry_at {
+            Some(at) => diesel::update(job)
+                .set((
+                    webhook_queue::attempts.eq(webhook_queue::attempts + 1),
+                    webhook_queue::next_attempt_at.eq(at),
+                ))
+                .execute(c),
+            None => diesel::delete(job).execute(c),
+        }
+    }
+}
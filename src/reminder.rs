@@ -0,0 +1,228 @@
+//! Digest emails of tasks which are overdue or haven't been done for long.
+
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rocket::{Orbit, Rocket, tokio};
+use rocket_sync_db_pools::ConnectionPool;
+
+use crate::models::household::Household;
+use crate::models::reminder::Reminder;
+use crate::models::schedule::DueStatus;
+use crate::models::task::{Task, TaskSummary};
+use crate::{DbConn, DbConnection};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+    /// Plain text, e.g. for a local SMTP sink.
+    None,
+    StartTls,
+    /// TLS from the start, usually on port 465.
+    Wrapper,
+}
+
+/// `[default.reminders]` table of `Rocket.toml`. Without it, no reminders
+/// are sent.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub smtp_host: String,
+    /// Defaults to the usual port of `tls`.
+    pub smtp_port: Option<u16>,
+    pub smtp_user: Option<String>,
+    pub smtp_password: Option<String>,
+    pub tls: Encryption,
+    pub from: String,
+    /// 0 is taken as 1, since the check can't run all the time.
+    pub check_interval_secs: u64,
+    /// Tasks without interval are reminded of when they haven't been done
+    /// for this many days.
+    pub stale_after_days: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            smtp_host: "localhost".to_string(),
+            smtp_port: None,
+            smtp_user: None,
+            smtp_password: None,
+            tls: Encryption::StartTls,
+            from: "last-date <last-date@localhost>".to_string(),
+            check_interval_secs: 60 * 60,
+            stale_after_days: 30,
+        }
+    }
+}
+
+impl Config {
+    /// Time between checks for reminders to send. Never zero.
+    pub(crate) fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs.max(1))
+    }
+
+    fn mailer(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+        let builder = match self.tls {
+            Encryption::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.smtp_host)
+            }
+            Encryption::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.smtp_host)
+                    .map_err(|e| e.to_string())?
+            }
+            Encryption::Wrapper => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.smtp_host)
+                .map_err(|e| e.to_string())?,
+        };
+        let builder = match self.smtp_port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+        let builder = match (&self.smtp_user, &self.smtp_password) {
+            (Some(user), Some(password)) => {
+                builder.credentials(Credentials::new(user.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+        Ok(builder.build())
+    }
+}
+
+/// Starts sending reminders periodically if `Rocket.toml` configures them.
+pub fn start(rocket: &Rocket<Orbit>) {
+    let config = match rocket.figment().extract_inner::<Config>("reminders") {
+        Ok(config) => config,
+        Err(e) if e.missing() => return,
+        Err(e) => {
+            log::error!("invalid reminders config: {e}");
+            return;
+        }
+    };
+    let Some(pool) = DbConn::pool(rocket).cloned() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.check_interval());
+        loop {
+            interval.tick().await;
+            if let Err(e) = send_reminders(&pool, &config).await {
+                log::error!("failed to send reminders: {e}");
+            }
+        }
+    });
+}
+
+/// Returns tasks which are overdue, or have no interval and haven't been
+/// done for `stale_after_days`.
+fn due(tasks: Vec<TaskSummary>, stale_after_days: i64, today: NaiveDate) -> Vec<TaskSummary> {
+    tasks
+        .into_iter()
+        .filter(|t| match t.status {
+            Some(status) => status == DueStatus::Overdue,
+            None => (today - t.task.updated_at.date()).num_days() >= stale_after_days,
+        })
+        .collect()
+}
+
+fn digest(household: &Household, tasks: &[TaskSummary]) -> (String, String) {
+    let subject = format!("{} task(s) to do in {}", tasks.len(), household.name);
+    let mut body = format!("These tasks of {} are waiting for you.\n\n", household.name);
+    for t in tasks {
+        body.push_str(&format!(
+            "- {} (last done {}",
+            t.task.name,
+            t.task.updated_at.format("%Y-%m-%d")
+        ));
+        if let Some(due) = t.next_due {
+            body.push_str(&format!(", due {}", due.format("%Y-%m-%d")));
+        }
+        body.push_str(")\n");
+    }
+    (subject, body)
+}
+
+/// Sends each household a digest of its tasks to do, once per task until
+/// it's done again. Returns the number of emails sent.
+pub async fn send_reminders(
+    pool: &ConnectionPool<DbConn, DbConnection>,
+    config: &Config,
+) -> Result<usize, String> {
+    let from: Mailbox = config.from.parse().map_err(|e| format!("from: {e}"))?;
+    let mailer = config.mailer()?;
+    let conn = pool.get().await.ok_or("no database connection")?;
+    let today = Local::now().date_naive();
+    let stale_after_days = config.stale_after_days;
+    let digests = conn
+        .run(move |c| {
+            let mut digests = Vec::new();
+            for (household, emails) in Household::all_with_emails(c)? {
+                if emails.is_empty() {
+                    continue;
+                }
+                // Archived tasks are out of season.
+                let tasks: Vec<Task> = Task::all_in(household.id, c)?
+                    .into_iter()
+                    .filter(|t| t.archived_at.is_none())
+                    .collect();
+                let tasks = Task::summarize(tasks, c)?;
+                let ids: Vec<i32> = tasks.iter().filter_map(|t| t.task.id).collect();
+                let sent = Reminder::sent(&ids, c)?;
+                let tasks: Vec<TaskSummary> = due(tasks, stale_after_days, today)
+                    .into_iter()
+                    .filter(|t| {
+                        t.task
+                            .id
+                            .is_some_and(|id| !sent.contains(&(id, t.task.updated_at)))
+                    })
+                    .collect();
+                if !tasks.is_empty() {
+                    digests.push((household, emails, tasks));
+                }
+            }
+            Ok::<_, diesel::result::Error>(digests)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    for (household, emails, tasks) in digests {
+        let (subject, body) = digest(&household, &tasks);
+        let mut message = Message::builder().from(from.clone()).subject(subject);
+        for email in &emails {
+            match email.parse::<Mailbox>() {
+                Ok(to) => message = message.to(to),
+                Err(e) => log::warn!("skipping email address {email}: {e}"),
+            }
+        }
+        let message = match message.body(body) {
+            Ok(message) => message,
+            Err(e) => {
+                log::error!("failed to make reminder for {}: {e}", household.name);
+                continue;
+            }
+        };
+        if let Err(e) = mailer.send(message).await {
+            log::error!("failed to send reminder for {}: {e}", household.name);
+            continue;
+        }
+        count += 1;
+        let sent_at = Local::now().naive_local();
+        let reminders: Vec<Reminder> = tasks
+            .iter()
+            .filter_map(|t| {
+                Some(Reminder {
+                    task_id: t.task.id?,
+                    updated_at: t.task.updated_at,
+                    sent_at,
+                })
+            })
+            .collect();
+        conn.run(move |c| Reminder::record(&reminders, c))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(count)
+}
@@ -1,2 +1,15 @@
+pub mod activity;
+pub mod api;
+pub mod backup;
+pub mod calendar;
+pub mod csv_file;
+pub mod error;
+pub mod household;
 pub mod label;
+pub mod metrics;
+pub mod stats;
 pub mod task;
+pub mod trash;
+pub mod undo;
+pub mod user;
+pub mod webhook;
@@ -0,0 +1,28 @@
+use crate::DbConn;
+use crate::models::audit::{Activity, ActivityQuery};
+use crate::models::household::Household;
+use crate::models::webhook::Event;
+
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Template;
+
+#[derive(Debug, Serialize)]
+struct IndexContext {
+    activities: Vec<Activity>,
+    query: ActivityQuery,
+    events: Vec<&'static str>,
+    actors: Vec<(i32, String)>,
+}
+
+#[get("/activity?<query..>")]
+pub async fn index(query: ActivityQuery, household: Household, conn: DbConn) -> Template {
+    Template::render(
+        "activity/index",
+        IndexContext {
+            activities: Activity::list(household.id, query.clone(), &conn).await,
+            query,
+            events: Event::ALL.iter().map(Event::as_str).collect(),
+            actors: Activity::actors(household.id, &conn).await,
+        },
+    )
+}
@@ -0,0 +1,97 @@
+//! Versioned JSON API mounted at `/api/v1`.
+
+pub mod label;
+pub mod task;
+
+use crate::error::Error;
+use crate::models::validation::FieldError;
+
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::{Json, json};
+
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest,
+    Unauthorized,
+    /// The user belongs to no household.
+    Forbidden,
+    NotFound,
+    Unprocessable(Vec<FieldError>),
+    Internal,
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let (status, body) = match self {
+            ApiError::BadRequest => (Status::BadRequest, json!({ "error": "bad request" })),
+            ApiError::Unauthorized => (Status::Unauthorized, json!({ "error": "please log in" })),
+            ApiError::Forbidden => (Status::Forbidden, json!({ "error": "no household" })),
+            ApiError::NotFound => (Status::NotFound, json!({ "error": "not found" })),
+            ApiError::Unprocessable(errors) => (
+                Status::UnprocessableEntity,
+                json!({ "error": "validation failed", "errors": errors }),
+            ),
+            ApiError::Internal => (
+                Status::InternalServerError,
+                json!({ "error": "the server failed" }),
+            ),
+        };
+        (status, Json(body)).respond_to(req)
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> ApiError {
+        match e {
+            Error::NotFound => ApiError::NotFound,
+            Error::Database(e) => {
+                log::error!("database error: {e}");
+                ApiError::Internal
+            }
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Fails with `Unprocessable` if there is any error.
+pub fn check(errors: Vec<FieldError>) -> ApiResult<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::Unprocessable(errors))
+    }
+}
+
+#[catch(400)]
+pub fn bad_request() -> ApiError {
+    ApiError::BadRequest
+}
+
+#[catch(401)]
+pub fn unauthorized() -> ApiError {
+    ApiError::Unauthorized
+}
+
+#[catch(403)]
+pub fn forbidden() -> ApiError {
+    ApiError::Forbidden
+}
+
+#[catch(404)]
+pub fn not_found() -> ApiError {
+    ApiError::NotFound
+}
+
+#[catch(422)]
+pub fn unprocessable() -> ApiError {
+    // Rocket gives up before our validation, e.g. with missing fields.
+    ApiError::Unprocessable(Vec::new())
+}
+
+#[catch(500)]
+pub fn internal() -> ApiError {
+    ApiError::Internal
+}
@@ -0,0 +1,77 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::label::{Label, LabelForm};
+use crate::models::task::{Task, TaskSummary};
+use crate::models::user::User;
+use crate::routes::api::{ApiError, ApiResult, check};
+
+use rocket::http::Status;
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+
+async fn find(id: i32, household_id: i32, conn: &DbConn) -> ApiResult<Label> {
+    Ok(Label::label_by_id(id, household_id, conn).await?)
+}
+
+#[get("/labels")]
+pub async fn list(household: Household, conn: DbConn) -> Json<Vec<Label>> {
+    Json(Label::all(household.id, &conn).await)
+}
+
+#[get("/labels/<id>")]
+pub async fn get(id: i32, household: Household, conn: DbConn) -> ApiResult<Json<Label>> {
+    find(id, household.id, &conn).await.map(Json)
+}
+
+#[get("/labels/<id>/tasks")]
+pub async fn tasks(
+    id: i32,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Json<Vec<TaskSummary>>> {
+    Ok(Json(Task::tasks_by_label(id, household.id, &conn).await?))
+}
+
+#[post("/labels", format = "json", data = "<label>")]
+pub async fn create(
+    label: Json<LabelForm>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Created<Json<Label>>> {
+    let label = label.into_inner();
+    check(label.validate())?;
+    let id = Label::insert(label, household.id, Some(user.id), &conn)
+        .await
+        .ok_or(ApiError::Internal)?;
+    let label = find(id, household.id, &conn).await?;
+    Ok(Created::new(format!("/api/v1/labels/{id}")).body(Json(label)))
+}
+
+#[put("/labels/<id>", format = "json", data = "<label>")]
+pub async fn update(
+    id: i32,
+    label: Json<LabelForm>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Json<Label>> {
+    find(id, household.id, &conn).await?;
+    let label = label.into_inner();
+    check(label.validate())?;
+    if !Label::update(id, household.id, label, Some(user.id), &conn).await {
+        return Err(ApiError::Internal);
+    }
+    let label = find(id, household.id, &conn).await?;
+    Ok(Json(label))
+}
+
+#[delete("/labels/<id>")]
+pub async fn delete(id: i32, user: User, household: Household, conn: DbConn) -> ApiResult<Status> {
+    find(id, household.id, &conn).await?;
+    if Label::delete_with_id(id, household.id, Some(user.id), &conn).await {
+        Ok(Status::NoContent)
+    } else {
+        Err(ApiError::Internal)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::task::{Task, TaskName, TaskSummary, TaskUpdate};
+use crate::models::user::User;
+use crate::routes::api::{ApiError, ApiResult, check};
+
+use rocket::http::Status;
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+
+#[derive(Debug, Deserialize)]
+pub struct TaskDone {
+    pub note: Option<String>,
+}
+
+async fn find(id: i32, household_id: i32, conn: &DbConn) -> ApiResult<TaskSummary> {
+    Ok(Task::summary_by_id(id, household_id, conn).await?)
+}
+
+#[get("/tasks")]
+pub async fn list(household: Household, conn: DbConn) -> Json<Vec<TaskSummary>> {
+    Json(Task::all_by_due(household.id, &conn).await)
+}
+
+#[get("/tasks/<id>")]
+pub async fn get(id: i32, household: Household, conn: DbConn) -> ApiResult<Json<TaskSummary>> {
+    find(id, household.id, &conn).await.map(Json)
+}
+
+#[post("/tasks", format = "json", data = "<task>")]
+pub async fn create(
+    task: Json<TaskName>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Created<Json<TaskSummary>>> {
+    let task = task.into_inner();
+    check(task.validate())?;
+    let id = Task::insert(task, household.id, Some(user.id), &conn)
+        .await
+        .ok_or(ApiError::Internal)?;
+    let task = find(id, household.id, &conn).await?;
+    Ok(Created::new(format!("/api/v1/tasks/{id}")).body(Json(task)))
+}
+
+#[put("/tasks/<id>", format = "json", data = "<task>")]
+pub async fn update(
+    id: i32,
+    task: Json<TaskUpdate>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Json<TaskSummary>> {
+    find(id, household.id, &conn).await?;
+    let task = task.into_inner();
+    check(task.validate())?;
+    if !Task::update(id, household.id, task, Some(user.id), &conn).await {
+        return Err(ApiError::Internal);
+    }
+    let task = find(id, household.id, &conn).await?;
+    Ok(Json(task))
+}
+
+#[delete("/tasks/<id>")]
+pub async fn delete(id: i32, user: User, household: Household, conn: DbConn) -> ApiResult<Status> {
+    find(id, household.id, &conn).await?;
+    if Task::delete_with_id(id, household.id, Some(user.id), &conn).await {
+        Ok(Status::NoContent)
+    } else {
+        Err(ApiError::Internal)
+    }
+}
+
+/// Same as "I did it today!" button. The body with `note` is optional.
+#[post("/tasks/<id>/done", data = "<done>")]
+pub async fn done(
+    id: i32,
+    done: Option<Json<TaskDone>>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> ApiResult<Json<TaskSummary>> {
+    find(id, household.id, &conn).await?;
+    let note = done.and_then(|d| d.into_inner().note);
+    if !Task::update_to_today(id, household.id, note, Some(user.id), &conn).await {
+        return Err(ApiError::Internal);
+    }
+    let task = find(id, household.id, &conn).await?;
+    Ok(Json(task))
+}
@@ -0,0 +1,92 @@
+use crate::DbConn;
+use crate::models::backup::{RestoreError, RestoreMode, Snapshot};
+use crate::models::household::Household;
+use crate::models::user::User;
+
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::http::Header;
+use rocket::request::FlashMessage;
+use rocket::response::{Flash, Redirect};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::tokio::io::AsyncReadExt;
+use rocket_dyn_templates::Template;
+
+#[derive(Responder)]
+pub struct BackupFile(Json<Snapshot>, Header<'static>);
+
+#[derive(FromForm)]
+pub struct Upload<'r> {
+    file: TempFile<'r>,
+    mode: RestoreMode,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexContext<'a, 'b> {
+    msg: Option<(&'a str, &'b str)>,
+}
+
+#[get("/backup.json")]
+pub async fn download(household: Household, conn: DbConn) -> BackupFile {
+    BackupFile(
+        Json(Snapshot::take(household.id, &conn).await),
+        Header::new(
+            "Content-Disposition",
+            "attachment; filename=\"last-date-backup.json\"",
+        ),
+    )
+}
+
+#[get("/backup")]
+pub fn index(_household: Household, msg: Option<FlashMessage<'_>>) -> Template {
+    Template::render(
+        "backup/index",
+        IndexContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+        },
+    )
+}
+
+#[post("/backup", data = "<upload>")]
+pub async fn restore(
+    user: User,
+    household: Household,
+    upload: Form<Upload<'_>>,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    let mut data = String::new();
+    let read = match upload.file.open().await {
+        Ok(mut file) => file.read_to_string(&mut data).await.is_ok(),
+        Err(_) => false,
+    };
+    if !read {
+        return Flash::warning(
+            Redirect::to("/backup"),
+            "Couldn't read the file as UTF-8 text.",
+        );
+    }
+    let restored = match Snapshot::parse(&data) {
+        Ok(snapshot) => {
+            snapshot
+                .restore(upload.mode, household.id, Some(user.id), &conn)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match restored {
+        Ok(r) => Flash::success(
+            Redirect::to("/"),
+            format!(
+                "Restored {} task(s) and {} new label(s).",
+                r.tasks, r.labels
+            ),
+        ),
+        Err(e) => {
+            if let RestoreError::Database(ref e) = e {
+                log::error!("database error: {e}");
+            }
+            Flash::warning(Redirect::to("/backup"), e.to_string())
+        }
+    }
+}
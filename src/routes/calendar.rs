@@ -0,0 +1,57 @@
+use crate::DbConn;
+use crate::calendar;
+use crate::error::Error;
+use crate::models::household::Household;
+use crate::models::label::Label;
+use crate::models::task::Task;
+
+use chrono::Utc;
+use rocket::http::ContentType;
+
+/// Calendar clients can't log in, so feeds are also unlocked by the token
+/// shown on the household page. Without either, feeds don't exist.
+async fn feed_household(
+    token: Option<String>,
+    household: Option<Household>,
+    conn: &DbConn,
+) -> Result<Household, Error> {
+    match token {
+        Some(token) => Household::by_calendar_token(token, conn).await,
+        None => household,
+    }
+    .ok_or(Error::NotFound)
+}
+
+#[get("/calendar.ics?<token>")]
+pub async fn feed(
+    token: Option<String>,
+    household: Option<Household>,
+    conn: DbConn,
+) -> Result<(ContentType, String), Error> {
+    let household = feed_household(token, household, &conn).await?;
+    let tasks = Task::all_by_due(household.id, &conn).await;
+    let labels = Label::all(household.id, &conn).await;
+    Ok((
+        ContentType::Calendar,
+        calendar::render(&household.name, &tasks, &labels, Utc::now()),
+    ))
+}
+
+#[get("/label/<id>/calendar.ics?<token>")]
+pub async fn label_feed(
+    id: i32,
+    token: Option<String>,
+    household: Option<Household>,
+    conn: DbConn,
+) -> Result<(ContentType, String), Error> {
+    let household = feed_household(token, household, &conn).await?;
+    let mut tasks = Task::tasks_by_label(id, household.id, &conn).await?;
+    tasks.retain(|t| t.task.archived_at.is_none());
+    let label = Label::label_by_id(id, household.id, &conn).await?;
+    let labels = Label::all(household.id, &conn).await;
+    let name = format!("{} - {}", household.name, label.name);
+    Ok((
+        ContentType::Calendar,
+        calendar::render(&name, &tasks, &labels, Utc::now()),
+    ))
+}
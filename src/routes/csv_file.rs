@@ -0,0 +1,77 @@
+use crate::DbConn;
+use crate::models::csv_file::{self, RowReport};
+use crate::models::household::Household;
+use crate::models::user::User;
+
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::http::Header;
+use rocket::serde::Serialize;
+use rocket::tokio::io::AsyncReadExt;
+use rocket_dyn_templates::Template;
+
+#[derive(Responder)]
+#[response(content_type = "text/csv")]
+pub struct CsvFile(String, Header<'static>);
+
+#[derive(FromForm)]
+pub struct Upload<'r> {
+    file: TempFile<'r>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportContext<'a> {
+    msg: Option<(&'a str, &'a str)>,
+    reports: Vec<RowReport>,
+}
+
+#[get("/export.csv")]
+pub async fn export(household: Household, conn: DbConn) -> CsvFile {
+    CsvFile(
+        csv_file::export(household.id, &conn).await,
+        Header::new("Content-Disposition", "attachment; filename=\"tasks.csv\""),
+    )
+}
+
+#[get("/import")]
+pub fn import_page(_household: Household) -> Template {
+    Template::render(
+        "csv/import",
+        ImportContext {
+            msg: None,
+            reports: Vec::new(),
+        },
+    )
+}
+
+#[post("/import", data = "<upload>")]
+pub async fn import(
+    user: User,
+    household: Household,
+    upload: Form<Upload<'_>>,
+    conn: DbConn,
+) -> Template {
+    let mut data = String::new();
+    let read = match upload.file.open().await {
+        Ok(mut file) => file.read_to_string(&mut data).await.is_ok(),
+        Err(_) => false,
+    };
+    let context = if read {
+        let reports = csv_file::import(&data, household.id, Some(user.id), &conn).await;
+        let msg = if reports.iter().all(|r| r.accepted) {
+            ("success", "All rows were imported.")
+        } else {
+            ("warning", "Some rows were rejected.")
+        };
+        ImportContext {
+            msg: Some(msg),
+            reports,
+        }
+    } else {
+        ImportContext {
+            msg: Some(("warning", "Couldn't read the file as UTF-8 text.")),
+            reports: Vec::new(),
+        }
+    };
+    Template::render("csv/import", context)
+}
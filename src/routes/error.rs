@@ -0,0 +1,25 @@
+use crate::error;
+
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket_dyn_templates::Template;
+
+#[catch(401)]
+pub fn unauthorized() -> Redirect {
+    Redirect::to("/login")
+}
+
+#[catch(403)]
+pub fn forbidden() -> Template {
+    error::page(Status::Forbidden)
+}
+
+#[catch(404)]
+pub fn not_found() -> Template {
+    error::page(Status::NotFound)
+}
+
+#[catch(500)]
+pub fn internal_error() -> Template {
+    error::page(Status::InternalServerError)
+}
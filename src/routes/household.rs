@@ -0,0 +1,134 @@
+use crate::DbConn;
+use crate::models::household::{Household, HouseholdForm, MemberForm};
+use crate::models::user::User;
+
+use rocket::form::Form;
+use rocket::http::{Cookie, CookieJar, Status};
+use rocket::outcome::try_outcome;
+use rocket::request::{FlashMessage, FromRequest, Outcome, Request};
+use rocket::response::{Flash, Redirect};
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Template;
+
+/// Private cookie holding the id of the household being viewed.
+const HOUSEHOLD_COOKIE: &str = "household_id";
+
+#[derive(Debug, Serialize)]
+struct IndexContext<'a, 'b> {
+    msg: Option<(&'a str, &'b str)>,
+    current: Household,
+    households: Vec<Household>,
+    members: Vec<String>,
+    calendar_token: Option<String>,
+    email: Option<String>,
+}
+
+/// Request guard for the current household of the logged in user. It is the
+/// one chosen last, or the oldest one the user belongs to.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Household {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Household, ()> {
+        let user = try_outcome!(req.guard::<User>().await);
+        let conn = try_outcome!(req.guard::<DbConn>().await);
+        let chosen: Option<i32> = req
+            .cookies()
+            .get_private(HOUSEHOLD_COOKIE)
+            .and_then(|c| c.value().parse().ok());
+        let mut households = Household::for_user(user.id, &conn).await;
+        let i = households
+            .iter()
+            .position(|h| Some(h.id) == chosen)
+            .unwrap_or_default();
+        if households.is_empty() {
+            Outcome::Error((Status::Forbidden, ()))
+        } else {
+            Outcome::Success(households.swap_remove(i))
+        }
+    }
+}
+
+#[get("/household")]
+pub async fn index(
+    user: User,
+    current: Household,
+    msg: Option<FlashMessage<'_>>,
+    conn: DbConn,
+) -> Template {
+    let households = Household::for_user(user.id, &conn).await;
+    let members = Household::member_names(current.id, &conn).await;
+    let calendar_token = Household::calendar_token(current.id, &conn).await;
+    Template::render(
+        "household/index",
+        IndexContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+            current,
+            households,
+            members,
+            calendar_token,
+            email: user.email,
+        },
+    )
+}
+
+#[post("/household", data = "<household_form>")]
+pub async fn new(
+    user: User,
+    household_form: Form<HouseholdForm>,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    let household = household_form.into_inner();
+    if let Some(e) = household.validate().first() {
+        return Flash::warning(Redirect::to("/household"), e.message);
+    }
+    match Household::insert(household, user.id, &conn).await {
+        Some(id) => {
+            cookies.add_private(Cookie::new(HOUSEHOLD_COOKIE, id.to_string()));
+            Flash::success(Redirect::to("/"), "New household added.")
+        }
+        None => Flash::warning(Redirect::to("/household"), "The server failed."),
+    }
+}
+
+/// Makes a new calendar feed link, replacing the old one.
+#[post("/household/calendar")]
+pub async fn rotate_calendar_token(current: Household, conn: DbConn) -> Flash<Redirect> {
+    if Household::rotate_calendar_token(current.id, &conn)
+        .await
+        .is_some()
+    {
+        Flash::success(
+            Redirect::to("/household"),
+            "New calendar feed link made. The old one no longer works.",
+        )
+    } else {
+        Flash::warning(Redirect::to("/household"), "The server failed.")
+    }
+}
+
+#[post("/household/<id>/switch")]
+pub async fn switch(id: i32, user: User, cookies: &CookieJar<'_>, conn: DbConn) -> Flash<Redirect> {
+    let households = Household::for_user(user.id, &conn).await;
+    if households.iter().any(|h| h.id == id) {
+        cookies.add_private(Cookie::new(HOUSEHOLD_COOKIE, id.to_string()));
+        Flash::success(Redirect::to("/"), "Switched household.")
+    } else {
+        Flash::warning(Redirect::to("/household"), "You aren't a member of it.")
+    }
+}
+
+#[post("/household/members", data = "<member_form>")]
+pub async fn add_member(
+    current: Household,
+    member_form: Form<MemberForm>,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    let member = member_form.into_inner();
+    if Household::add_member(current.id, member.user_name, &conn).await {
+        Flash::success(Redirect::to("/household"), "New member added.")
+    } else {
+        Flash::warning(Redirect::to("/household"), "No such user.")
+    }
+}
@@ -1,8 +1,13 @@
 use crate::DbConn;
+use crate::error::Error;
+use crate::models::household::Household;
 use crate::models::label::{Label, LabelForm};
+use crate::models::undo::Undo;
+use crate::models::user::User;
+use crate::routes::undo;
 
-use regex::Regex;
 use rocket::form::Form;
+use rocket::http::CookieJar;
 use rocket::request::FlashMessage;
 use rocket::response::{Flash, Redirect};
 use rocket::serde::Serialize;
@@ -12,6 +17,8 @@ use rocket_dyn_templates::Template;
 struct IndexContext<'a, 'b> {
     msg: Option<(&'a str, &'b str)>,
     labels: Vec<Label>,
+    /// Id of the undo offered with `msg`.
+    undo: Option<i32>,
 }
 #[derive(Debug, Serialize)]
 struct SingleContext {
@@ -24,54 +31,56 @@ struct UpdateContext<'a, 'b> {
 }
 
 impl<'a, 'b> IndexContext<'a, 'b> {
-    pub async fn err(conn: &DbConn, msg: &'a str) -> IndexContext<'static, 'a> {
-        IndexContext {
-            msg: Some(("warning", msg)),
-            labels: Label::all(conn).await,
-        }
-    }
-
-    pub async fn raw(conn: &DbConn, msg: Option<(&'a str, &'b str)>) -> IndexContext<'a, 'b> {
+    pub async fn raw(
+        household_id: i32,
+        conn: &DbConn,
+        msg: Option<(&'a str, &'b str)>,
+        undo: Option<i32>,
+    ) -> IndexContext<'a, 'b> {
         IndexContext {
             msg,
-            labels: Label::all(conn).await,
+            labels: Label::all(household_id, conn).await,
+            undo,
         }
     }
 }
 
 impl SingleContext {
-    pub async fn raw(id: i32, conn: &DbConn) -> SingleContext {
-        SingleContext {
-            label: Label::label_by_id(id, conn).await,
-        }
+    pub async fn raw(id: i32, household_id: i32, conn: &DbConn) -> Result<SingleContext, Error> {
+        Ok(SingleContext {
+            label: Label::label_by_id(id, household_id, conn).await?,
+        })
     }
 }
 
 impl<'a, 'b> UpdateContext<'a, 'b> {
     pub async fn raw(
         id: i32,
+        household_id: i32,
         conn: &DbConn,
         msg: Option<(&'a str, &'b str)>,
-    ) -> UpdateContext<'a, 'b> {
-        UpdateContext {
+    ) -> Result<UpdateContext<'a, 'b>, Error> {
+        Ok(UpdateContext {
             msg,
-            label: Label::label_by_id(id, conn).await,
-        }
+            label: Label::label_by_id(id, household_id, conn).await?,
+        })
     }
 }
 
 #[post("/label", data = "<label_form>")]
-pub async fn new(label_form: Form<LabelForm>, conn: DbConn) -> Flash<Redirect> {
+pub async fn new(
+    label_form: Form<LabelForm>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Flash<Redirect> {
     let label = label_form.into_inner();
-    let color_code_regex = Regex::new(r"#[[:xdigit:]]{6}$").unwrap();
-    if label.name.is_empty() {
-        Flash::warning(Redirect::to("/label"), "Please input label name.")
-    } else if label.color.is_empty() || !color_code_regex.is_match(&label.color) {
-        Flash::warning(
-            Redirect::to("/label"),
-            "Please input label color with hex format.",
-        )
-    } else if Label::insert(label, &conn).await {
+    if let Some(e) = label.validate().first() {
+        Flash::warning(Redirect::to("/label"), e.message)
+    } else if Label::insert(label, household.id, Some(user.id), &conn)
+        .await
+        .is_some()
+    {
         Flash::success(Redirect::to("/label"), "New label added.")
     } else {
         Flash::warning(Redirect::to("/label"), "The server failed.")
@@ -79,29 +88,38 @@ pub async fn new(label_form: Form<LabelForm>, conn: DbConn) -> Flash<Redirect> {
 }
 
 #[get("/label")]
-pub async fn index(msg: Option<FlashMessage<'_>>, conn: DbConn) -> Template {
+pub async fn index(
+    msg: Option<FlashMessage<'_>>,
+    household: Household,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Template {
+    let undo = undo::take(cookies);
     Template::render(
         "label/index",
         match msg {
-            Some(ref msg) => IndexContext::raw(&conn, Some((msg.kind(), msg.message()))).await,
-            None => IndexContext::raw(&conn, None).await,
+            Some(ref msg) => {
+                IndexContext::raw(household.id, &conn, Some((msg.kind(), msg.message())), undo)
+                    .await
+            }
+            None => IndexContext::raw(household.id, &conn, None, undo).await,
         },
     )
 }
 
 #[post("/label/<id>", data = "<label_form>")]
-pub async fn update(id: i32, label_form: Form<LabelForm>, conn: DbConn) -> Flash<Redirect> {
+pub async fn update(
+    id: i32,
+    label_form: Form<LabelForm>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Flash<Redirect> {
     let label = label_form.into_inner();
-    let color_code_regex = Regex::new(r"#[[:xdigit:]]{6}$").unwrap();
     let redirect_url = format!("/label/{id}/edit");
-    if label.name.is_empty() {
-        Flash::warning(Redirect::to(redirect_url), "Please input label name.")
-    } else if label.color.is_empty() || !color_code_regex.is_match(&label.color) {
-        Flash::warning(
-            Redirect::to(redirect_url),
-            "Please input label color with hex format.",
-        )
-    } else if Label::update(id, label, &conn).await {
+    if let Some(e) = label.validate().first() {
+        Flash::warning(Redirect::to(redirect_url), e.message)
+    } else if Label::update(id, household.id, label, Some(user.id), &conn).await {
         Flash::success(Redirect::to(redirect_url), "Label is updated.")
     } else {
         Flash::warning(Redirect::to(redirect_url), "The server failed.")
@@ -109,32 +127,46 @@ pub async fn update(id: i32, label_form: Form<LabelForm>, conn: DbConn) -> Flash
 }
 
 #[get("/label/<id>/edit", rank = 0)]
-pub async fn edit(id: i32, msg: Option<FlashMessage<'_>>, conn: DbConn) -> Template {
-    Template::render(
+pub async fn edit(
+    id: i32,
+    msg: Option<FlashMessage<'_>>,
+    household: Household,
+    conn: DbConn,
+) -> Result<Template, Error> {
+    Ok(Template::render(
         "label/edit",
         match msg {
-            Some(ref msg) => UpdateContext::raw(id, &conn, Some((msg.kind(), msg.message()))).await,
-            None => UpdateContext::raw(id, &conn, None).await,
+            Some(ref msg) => {
+                UpdateContext::raw(id, household.id, &conn, Some((msg.kind(), msg.message())))
+                    .await?
+            }
+            None => UpdateContext::raw(id, household.id, &conn, None).await?,
         },
-    )
+    ))
 }
 
 #[get("/label/<id>/confirm")]
-pub async fn confirm(id: i32, conn: DbConn) -> Template {
-    Template::render("label/confirm", SingleContext::raw(id, &conn).await)
+pub async fn confirm(id: i32, household: Household, conn: DbConn) -> Result<Template, Error> {
+    Ok(Template::render(
+        "label/confirm",
+        SingleContext::raw(id, household.id, &conn).await?,
+    ))
 }
 
 #[delete("/label/<id>")]
-pub async fn delete(id: i32, conn: DbConn) -> Result<Flash<Redirect>, Template> {
-    if Label::delete_with_id(id, &conn).await {
-        Ok(Flash::success(
-            Redirect::to("/label"),
-            "Your label was deleted.",
-        ))
-    } else {
-        Err(Template::render(
-            "label/index",
-            IndexContext::err(&conn, "Couldn't delete label.").await,
-        ))
+pub async fn delete(
+    id: i32,
+    user: User,
+    household: Household,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Result<Flash<Redirect>, Error> {
+    let (label, task_ids) = Label::take(id, household.id, Some(user.id), &conn).await?;
+    if let Some(undo) = Undo::label_deletion(label, task_ids) {
+        undo::offer(undo, household.id, cookies, &conn).await;
     }
+    Ok(Flash::success(
+        Redirect::to("/label"),
+        "Your label was deleted.",
+    ))
 }
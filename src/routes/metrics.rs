@@ -0,0 +1,69 @@
+use crate::DbConn;
+use crate::metrics::{Config, Metrics};
+use crate::models::household::Household;
+use crate::models::user::User;
+
+use rocket::State;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::status::Custom;
+use subtle::ConstantTimeEq;
+
+/// Token of the `Authorization: Bearer` header, if any.
+pub struct BearerToken(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BearerToken {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<BearerToken, ()> {
+        let token = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(|t| t.trim().to_string());
+        Outcome::Success(BearerToken(token))
+    }
+}
+
+impl BearerToken {
+    /// Whether the token is `expected`, compared in constant time.
+    fn is(&self, expected: &str) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|t| t.as_bytes().ct_eq(expected.as_bytes()).into())
+    }
+}
+
+/// Metrics in the Prometheus text format, for scrapers with the configured
+/// token, or for signed-in users if there's none, who only get the task
+/// metrics of their households. Failures are answered in plain text for
+/// scrapers rather than by the catchers for browsers.
+#[get("/metrics")]
+pub async fn index(
+    token: BearerToken,
+    user: Option<User>,
+    config: &State<Config>,
+    metrics: &State<Metrics>,
+    conn: DbConn,
+) -> Result<(ContentType, String), Custom<String>> {
+    let households = match (&config.token, user) {
+        (Some(expected), _) if token.is(expected) => None,
+        (None, Some(user)) => {
+            let households = Household::for_user(user.id, &conn).await;
+            Some(households.into_iter().map(|h| h.id).collect())
+        }
+        _ => return Err(Custom(Status::Unauthorized, "Unauthorized".to_string())),
+    };
+    let text = metrics.render(households, &conn).await.map_err(|e| {
+        log::error!("failed to read metrics: {e}");
+        Custom(
+            Status::InternalServerError,
+            "The server failed.".to_string(),
+        )
+    })?;
+    Ok((
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        text,
+    ))
+}
@@ -0,0 +1,10 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::stats::Stats;
+
+use rocket_dyn_templates::Template;
+
+#[get("/stats")]
+pub async fn index(household: Household, conn: DbConn) -> Template {
+    Template::render("stats/index", Stats::of(household.id, &conn).await)
+}
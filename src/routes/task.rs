@@ -1,8 +1,18 @@
 use crate::DbConn;
+use crate::chart;
+use crate::error::Error;
+use crate::models::completion::Completion;
+use crate::models::household::Household;
 use crate::models::label::Label;
-use crate::models::task::{Task, TaskName, TaskUpdate};
+use crate::models::search::SearchResult;
+use crate::models::task::{Task, TaskName, TaskQuery, TaskSummary, TaskUpdate};
+use crate::models::undo::Undo;
+use crate::models::user::User;
+use crate::routes::undo;
 
+use chrono::{Local, NaiveDate};
 use rocket::form::Form;
+use rocket::http::CookieJar;
 use rocket::request::FlashMessage;
 use rocket::response::{Flash, Redirect};
 use rocket::serde::Serialize;
@@ -11,136 +21,300 @@ use rocket_dyn_templates::Template;
 #[derive(Debug, Serialize)]
 struct IndexContext<'a, 'b> {
     msg: Option<(&'a str, &'b str)>,
-    tasks: Vec<Task>,
+    household: Household,
+    tasks: Vec<TaskSummary>,
     labels: Vec<Label>,
+    query: TaskQuery,
+    /// Id of the undo offered with `msg`.
+    undo: Option<i32>,
 }
 #[derive(Debug, Serialize)]
 struct SingleContext<'a, 'b> {
     msg: Option<(&'a str, &'b str)>,
-    task: Task,
+    task: TaskSummary,
     labels: Vec<Label>,
+    completions: Vec<Completion>,
+    today: NaiveDate,
+    /// SVG of the completions by day.
+    heatmap: String,
+    /// SVG of the days between completions.
+    intervals: Option<String>,
 }
 #[derive(Debug, Serialize)]
 struct ByLabelContext {
-    tasks: Vec<Task>,
+    tasks: Vec<TaskSummary>,
     label: Label,
+    /// SVG of the completions of the tasks by day.
+    heatmap: String,
+    /// SVGs of the days between completions of each task with any.
+    intervals: Vec<TaskChart>,
+}
+#[derive(Debug, Serialize)]
+struct TaskChart {
+    id: Option<i32>,
+    name: String,
+    svg: String,
+}
+#[derive(Debug, Serialize)]
+struct SearchContext {
+    q: String,
+    results: Vec<SearchResult>,
+    labels: Vec<Label>,
 }
 
 impl<'a, 'b> IndexContext<'a, 'b> {
-    pub async fn err(conn: &DbConn, msg: &'a str) -> IndexContext<'static, 'a> {
-        let tasks = Task::all(conn).await;
-        let labels = Label::all(conn).await;
+    pub async fn raw(
+        household: Household,
+        query: TaskQuery,
+        conn: &DbConn,
+        msg: Option<(&'a str, &'b str)>,
+        undo: Option<i32>,
+    ) -> IndexContext<'a, 'b> {
+        let tasks = Task::list(household.id, query.clone(), conn).await;
+        let labels = Label::all(household.id, conn).await;
         IndexContext {
-            msg: Some(("warning", msg)),
+            msg,
+            household,
             tasks,
             labels,
+            query,
+            undo,
         }
     }
-
-    pub async fn raw(conn: &DbConn, msg: Option<(&'a str, &'b str)>) -> IndexContext<'a, 'b> {
-        let tasks = Task::all(conn).await;
-        let labels = Label::all(conn).await;
-        IndexContext { msg, tasks, labels }
-    }
 }
 
 impl<'a, 'b> SingleContext<'a, 'b> {
     pub async fn raw(
         id: i32,
+        household_id: i32,
         conn: &DbConn,
         msg: Option<(&'a str, &'b str)>,
-    ) -> SingleContext<'a, 'b> {
-        let task = Task::task_by_id(id, conn).await;
-        let labels = Label::all(conn).await;
-        SingleContext { msg, task, labels }
+    ) -> Result<SingleContext<'a, 'b>, Error> {
+        let task = Task::summary_by_id(id, household_id, conn).await?;
+        let labels = Label::all(household_id, conn).await;
+        let completions = Completion::for_task(id, conn).await;
+        let today = Local::now().date_naive();
+        let dates: Vec<NaiveDate> = completions
+            .iter()
+            .rev()
+            .map(|c| c.completed_at.date())
+            .collect();
+        Ok(SingleContext {
+            msg,
+            task,
+            labels,
+            completions,
+            today,
+            heatmap: chart::heatmap(&dates, today),
+            intervals: chart::intervals(&dates),
+        })
     }
 }
 
 impl ByLabelContext {
-    pub async fn raw(label_id: i32, conn: &DbConn) -> ByLabelContext {
-        let tasks = Task::tasks_by_label(label_id, conn).await;
-        let label = Label::label_by_id(label_id, conn).await;
-        ByLabelContext { tasks, label }
+    pub async fn raw(
+        label_id: i32,
+        household_id: i32,
+        conn: &DbConn,
+    ) -> Result<ByLabelContext, Error> {
+        let tasks = Task::tasks_by_label(label_id, household_id, conn).await?;
+        let label = Label::label_by_id(label_id, household_id, conn).await?;
+        let ids = tasks.iter().filter_map(|t| t.task.id).collect();
+        let mut dates = Completion::dates_of(ids, conn).await;
+        let mut all_dates: Vec<NaiveDate> = dates.values().flatten().copied().collect();
+        all_dates.sort_unstable();
+        let intervals = tasks
+            .iter()
+            .filter_map(|t| {
+                let svg = chart::intervals(&dates.remove(&t.task.id?)?)?;
+                Some(TaskChart {
+                    id: t.task.id,
+                    name: t.task.name.clone(),
+                    svg,
+                })
+            })
+            .collect();
+        Ok(ByLabelContext {
+            tasks,
+            label,
+            heatmap: chart::heatmap(&all_dates, Local::now().date_naive()),
+            intervals,
+        })
     }
 }
 
 #[post("/", data = "<task_form>")]
-pub async fn new(task_form: Form<TaskName>, conn: DbConn) -> Flash<Redirect> {
+pub async fn new(
+    task_form: Form<TaskName>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Flash<Redirect> {
     let task = task_form.into_inner();
-    if task.name.is_empty() {
-        Flash::warning(Redirect::to("/"), "Please input task name.")
-    } else if Task::insert(task, &conn).await {
+    if let Some(e) = task.validate().first() {
+        Flash::warning(Redirect::to("/"), e.message)
+    } else if Task::insert(task, household.id, Some(user.id), &conn)
+        .await
+        .is_some()
+    {
         Flash::success(Redirect::to("/"), "New task added.")
     } else {
         Flash::warning(Redirect::to("/"), "The server failed.")
     }
 }
 
-#[get("/")]
-pub async fn index(msg: Option<FlashMessage<'_>>, conn: DbConn) -> Template {
+#[get("/?<query..>")]
+pub async fn index(
+    query: TaskQuery,
+    msg: Option<FlashMessage<'_>>,
+    household: Household,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Template {
+    let undo = undo::take(cookies);
     Template::render(
         "task/index",
         match msg {
-            Some(ref msg) => IndexContext::raw(&conn, Some((msg.kind(), msg.message()))).await,
-            None => IndexContext::raw(&conn, None).await,
+            Some(ref msg) => {
+                IndexContext::raw(
+                    household,
+                    query,
+                    &conn,
+                    Some((msg.kind(), msg.message())),
+                    undo,
+                )
+                .await
+            }
+            None => IndexContext::raw(household, query, &conn, None, undo).await,
         },
     )
 }
 
+#[get("/search?<q>")]
+pub async fn search(q: Option<String>, household: Household, conn: DbConn) -> Template {
+    let q = q.unwrap_or_default();
+    let results = SearchResult::find(q.clone(), household.id, &conn).await;
+    let labels = Label::all(household.id, &conn).await;
+    Template::render("task/search", SearchContext { q, results, labels })
+}
+
 #[get("/label/<id>", rank = 0)]
-pub async fn by_label(id: i32, conn: DbConn) -> Template {
-    Template::render("task/bylabel", ByLabelContext::raw(id, &conn).await)
+pub async fn by_label(id: i32, household: Household, conn: DbConn) -> Result<Template, Error> {
+    Ok(Template::render(
+        "task/bylabel",
+        ByLabelContext::raw(id, household.id, &conn).await?,
+    ))
 }
 
 #[post("/<id>/date", rank = 1)]
-pub async fn update_date(id: i32, conn: DbConn) -> Flash<Redirect> {
-    if Task::update_to_today(id, &conn).await {
-        Flash::success(
-            Redirect::to("/"),
-            "\"Last updated\" date is updated to today.",
-        )
-    } else {
-        Flash::warning(Redirect::to("/"), "The server failed.")
-    }
+pub async fn update_date(
+    id: i32,
+    user: User,
+    household: Household,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Result<Flash<Redirect>, Error> {
+    let (completion_id, previous) =
+        Task::complete_now(id, household.id, None, Some(user.id), &conn).await?;
+    let undo = Undo::Completion {
+        task_id: id,
+        completion_id,
+        previous,
+    };
+    undo::offer(undo, household.id, cookies, &conn).await;
+    Ok(Flash::success(
+        Redirect::to("/"),
+        "\"Last updated\" date is updated to today.",
+    ))
 }
 
 #[get("/<id>")]
-pub async fn edit(id: i32, msg: Option<FlashMessage<'_>>, conn: DbConn) -> Template {
-    Template::render(
+pub async fn edit(
+    id: i32,
+    msg: Option<FlashMessage<'_>>,
+    household: Household,
+    conn: DbConn,
+) -> Result<Template, Error> {
+    Ok(Template::render(
         "task/edit",
         match msg {
-            Some(ref msg) => SingleContext::raw(id, &conn, Some((msg.kind(), msg.message()))).await,
-            None => SingleContext::raw(id, &conn, None).await,
+            Some(ref msg) => {
+                SingleContext::raw(id, household.id, &conn, Some((msg.kind(), msg.message())))
+                    .await?
+            }
+            None => SingleContext::raw(id, household.id, &conn, None).await?,
         },
-    )
+    ))
 }
 
 #[post("/<id>", data = "<task_update_form>")]
-pub async fn update(id: i32, task_update_form: Form<TaskUpdate>, conn: DbConn) -> Flash<Redirect> {
+pub async fn update(
+    id: i32,
+    task_update_form: Form<TaskUpdate>,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Flash<Redirect> {
     let task = task_update_form.into_inner();
     let redirect_url = format!("/{id}");
-    if task.name.is_empty() {
-        Flash::warning(Redirect::to(redirect_url), "Please input task name.")
-    } else if Task::update(id, task, &conn).await {
+    if let Some(e) = task.validate().first() {
+        Flash::warning(Redirect::to(redirect_url), e.message)
+    } else if Task::update(id, household.id, task, Some(user.id), &conn).await {
         Flash::success(Redirect::to(redirect_url), "Your task was updated.")
     } else {
         Flash::warning(Redirect::to(redirect_url), "The server failed.")
     }
 }
 
+#[post("/<id>/archive", rank = 1)]
+pub async fn archive(
+    id: i32,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Result<Flash<Redirect>, Error> {
+    Task::set_archived(id, household.id, true, Some(user.id), &conn).await?;
+    Ok(Flash::success(
+        Redirect::to(format!("/{id}")),
+        "Your task was archived. It's hidden from the index page.",
+    ))
+}
+
+#[post("/<id>/unarchive", rank = 1)]
+pub async fn unarchive(
+    id: i32,
+    user: User,
+    household: Household,
+    conn: DbConn,
+) -> Result<Flash<Redirect>, Error> {
+    Task::set_archived(id, household.id, false, Some(user.id), &conn).await?;
+    Ok(Flash::success(
+        Redirect::to(format!("/{id}")),
+        "Your task is back on the index page.",
+    ))
+}
+
 #[get("/<id>/confirm", rank = 1)]
-pub async fn confirm(id: i32, conn: DbConn) -> Template {
-    Template::render("task/confirm", SingleContext::raw(id, &conn, None).await)
+pub async fn confirm(id: i32, household: Household, conn: DbConn) -> Result<Template, Error> {
+    Ok(Template::render(
+        "task/confirm",
+        SingleContext::raw(id, household.id, &conn, None).await?,
+    ))
 }
 
 #[delete("/<id>")]
-pub async fn delete(id: i32, conn: DbConn) -> Result<Flash<Redirect>, Template> {
-    if Task::delete_with_id(id, &conn).await {
-        Ok(Flash::success(Redirect::to("/"), "Your task was deleted."))
-    } else {
-        Err(Template::render(
-            "task/index",
-            IndexContext::err(&conn, "Couldn't delete task.").await,
-        ))
-    }
+pub async fn delete(
+    id: i32,
+    user: User,
+    household: Household,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Result<Flash<Redirect>, Error> {
+    Task::move_to_trash(id, household.id, Some(user.id), &conn).await?;
+    let undo = Undo::TaskDeletion { task_id: id };
+    undo::offer(undo, household.id, cookies, &conn).await;
+    Ok(Flash::success(
+        Redirect::to("/"),
+        "Your task was moved to the trash.",
+    ))
 }
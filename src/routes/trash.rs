@@ -0,0 +1,44 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::task::Task;
+use crate::models::user::User;
+
+use rocket::request::FlashMessage;
+use rocket::response::{Flash, Redirect};
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Template;
+
+#[derive(Debug, Serialize)]
+struct IndexContext<'a, 'b> {
+    msg: Option<(&'a str, &'b str)>,
+    tasks: Vec<Task>,
+}
+
+#[get("/trash")]
+pub async fn index(msg: Option<FlashMessage<'_>>, household: Household, conn: DbConn) -> Template {
+    Template::render(
+        "trash/index",
+        IndexContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+            tasks: Task::trash(household.id, &conn).await,
+        },
+    )
+}
+
+#[post("/trash/<id>/restore")]
+pub async fn restore(id: i32, user: User, household: Household, conn: DbConn) -> Flash<Redirect> {
+    if Task::restore(id, household.id, Some(user.id), &conn).await {
+        Flash::success(Redirect::to("/trash"), "Your task was restored.")
+    } else {
+        Flash::warning(Redirect::to("/trash"), "Couldn't restore task.")
+    }
+}
+
+#[delete("/trash/<id>")]
+pub async fn purge(id: i32, user: User, household: Household, conn: DbConn) -> Flash<Redirect> {
+    if Task::purge(id, household.id, Some(user.id), &conn).await {
+        Flash::success(Redirect::to("/trash"), "Your task was deleted for good.")
+    } else {
+        Flash::warning(Redirect::to("/trash"), "Couldn't delete task.")
+    }
+}
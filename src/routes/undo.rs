@@ -0,0 +1,32 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::undo::Undo;
+use crate::models::user::User;
+
+use rocket::http::{Cookie, CookieJar};
+use rocket::response::{Flash, Redirect};
+
+/// Private cookie with the id of the undo offered on the next page.
+const UNDO_COOKIE: &str = "undo";
+
+/// Offers to undo the change next to the flash message of the next page.
+pub async fn offer(undo: Undo, household_id: i32, cookies: &CookieJar<'_>, conn: &DbConn) {
+    if let Some(id) = undo.save(household_id, conn).await {
+        cookies.add_private(Cookie::new(UNDO_COOKIE, id.to_string()));
+    }
+}
+
+/// Returns the id of the undo offered by the previous request, once.
+pub fn take(cookies: &CookieJar<'_>) -> Option<i32> {
+    let cookie = cookies.get_private(UNDO_COOKIE)?;
+    cookies.remove_private(UNDO_COOKIE);
+    cookie.value().parse().ok()
+}
+
+#[post("/undo/<id>")]
+pub async fn undo(id: i32, user: User, household: Household, conn: DbConn) -> Flash<Redirect> {
+    let Some(undo) = Undo::apply(id, household.id, Some(user.id), &conn).await else {
+        return Flash::warning(Redirect::to("/"), "It's too late to undo it.");
+    };
+    Flash::success(Redirect::to(undo.page()), "Undone.")
+}
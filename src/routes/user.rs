@@ -0,0 +1,144 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::user::{Credentials, EmailForm, User};
+
+use rocket::form::Form;
+use rocket::http::{Cookie, CookieJar, Status};
+use rocket::outcome::try_outcome;
+use rocket::request::{FlashMessage, FromRequest, Outcome, Request};
+use rocket::response::{Flash, Redirect};
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Template;
+
+/// Private cookie holding the id of the logged in user.
+const USER_COOKIE: &str = "user_id";
+
+#[derive(Debug, Serialize)]
+struct LoginContext<'a, 'b> {
+    msg: Option<(&'a str, &'b str)>,
+}
+
+/// Request guard for logged in users. Fails with `401 Unauthorized`, which
+/// is caught to redirect to the login page.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for User {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<User, ()> {
+        let Some(id) = req
+            .cookies()
+            .get_private(USER_COOKIE)
+            .and_then(|c| c.value().parse().ok())
+        else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+        let conn = try_outcome!(req.guard::<DbConn>().await);
+        match User::user_by_id(id, &conn).await {
+            Ok(user) => Outcome::Success(user),
+            Err(_) => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+fn log_in(cookies: &CookieJar<'_>, user_id: i32) {
+    cookies.add_private(Cookie::new(USER_COOKIE, user_id.to_string()));
+}
+
+/// Anyone may sign up until the first user exists. After that, only logged
+/// in users can add accounts.
+async fn can_sign_up(user: &Option<User>, conn: &DbConn) -> bool {
+    user.is_some() || User::count(conn).await == 0
+}
+
+#[get("/login")]
+pub async fn login_page(msg: Option<FlashMessage<'_>>, conn: DbConn) -> Result<Template, Redirect> {
+    if User::count(&conn).await == 0 {
+        return Err(Redirect::to("/signup"));
+    }
+    Ok(Template::render(
+        "user/login",
+        LoginContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+        },
+    ))
+}
+
+#[post("/login", data = "<credentials>")]
+pub async fn login(
+    credentials: Form<Credentials>,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    match User::authenticate(credentials.into_inner(), &conn).await {
+        Some(user) => {
+            log_in(cookies, user.id);
+            Flash::success(Redirect::to("/"), "Logged in.")
+        }
+        None => Flash::warning(Redirect::to("/login"), "Wrong user name or password."),
+    }
+}
+
+#[post("/logout")]
+pub fn logout(cookies: &CookieJar<'_>) -> Flash<Redirect> {
+    cookies.remove_private(USER_COOKIE);
+    Flash::success(Redirect::to("/login"), "Logged out.")
+}
+
+#[get("/signup")]
+pub async fn signup_page(
+    user: Option<User>,
+    msg: Option<FlashMessage<'_>>,
+    conn: DbConn,
+) -> Result<Template, Redirect> {
+    if !can_sign_up(&user, &conn).await {
+        return Err(Redirect::to("/login"));
+    }
+    Ok(Template::render(
+        "user/signup",
+        LoginContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+        },
+    ))
+}
+
+#[post("/signup", data = "<credentials>")]
+pub async fn signup(
+    user: Option<User>,
+    credentials: Form<Credentials>,
+    cookies: &CookieJar<'_>,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    if !can_sign_up(&user, &conn).await {
+        return Flash::warning(Redirect::to("/login"), "Please log in first.");
+    }
+    let credentials = credentials.into_inner();
+    if let Some(e) = credentials.validate().first() {
+        return Flash::warning(Redirect::to("/signup"), e.message);
+    }
+    match User::insert(credentials, &conn).await {
+        Some(id) => {
+            if user.is_none() {
+                // The first user gets the tasks made before user accounts
+                // existed, e.g. those kept by the households migration.
+                if let Err(e) = Household::adopt_orphans(id, &conn).await {
+                    log::error!("failed to adopt households: {e}");
+                }
+                log_in(cookies, id);
+            }
+            Flash::success(Redirect::to("/"), "New user added.")
+        }
+        None => Flash::warning(Redirect::to("/signup"), "The user name is already taken."),
+    }
+}
+
+#[post("/user/email", data = "<email_form>")]
+pub async fn set_email(user: User, email_form: Form<EmailForm>, conn: DbConn) -> Flash<Redirect> {
+    let email = email_form.into_inner();
+    if let Some(e) = email.validate().first() {
+        Flash::warning(Redirect::to("/household"), e.message)
+    } else if User::set_email(user.id, email, &conn).await {
+        Flash::success(Redirect::to("/household"), "Your email address is updated.")
+    } else {
+        Flash::warning(Redirect::to("/household"), "The server failed.")
+    }
+}
@@ -0,0 +1,55 @@
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::webhook::{Delivery, Event, Webhook, WebhookForm};
+
+use rocket::form::Form;
+use rocket::request::FlashMessage;
+use rocket::response::{Flash, Redirect};
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Template;
+
+#[derive(Debug, Serialize)]
+struct IndexContext<'a, 'b> {
+    msg: Option<(&'a str, &'b str)>,
+    webhooks: Vec<Webhook>,
+    deliveries: Vec<Delivery>,
+    events: Vec<&'static str>,
+}
+
+#[get("/webhooks")]
+pub async fn index(msg: Option<FlashMessage<'_>>, household: Household, conn: DbConn) -> Template {
+    Template::render(
+        "webhook/index",
+        IndexContext {
+            msg: msg.as_ref().map(|m| (m.kind(), m.message())),
+            webhooks: Webhook::all(household.id, &conn).await,
+            deliveries: Delivery::recent(household.id, &conn).await,
+            events: Event::ALL.iter().map(Event::as_str).collect(),
+        },
+    )
+}
+
+#[post("/webhooks", data = "<webhook_form>")]
+pub async fn new(
+    webhook_form: Form<WebhookForm>,
+    household: Household,
+    conn: DbConn,
+) -> Flash<Redirect> {
+    let webhook = webhook_form.into_inner();
+    if let Some(e) = webhook.validate().first() {
+        Flash::warning(Redirect::to("/webhooks"), e.message)
+    } else if Webhook::insert(webhook, household.id, &conn).await {
+        Flash::success(Redirect::to("/webhooks"), "New webhook added.")
+    } else {
+        Flash::warning(Redirect::to("/webhooks"), "The server failed.")
+    }
+}
+
+#[delete("/webhooks/<id>")]
+pub async fn delete(id: i32, household: Household, conn: DbConn) -> Flash<Redirect> {
+    if Webhook::delete_with_id(id, household.id, &conn).await {
+        Flash::success(Redirect::to("/webhooks"), "Your webhook was deleted.")
+    } else {
+        Flash::warning(Redirect::to("/webhooks"), "Couldn't delete webhook.")
+    }
+}
@@ -1,12 +1,34 @@
-use super::models::label::Label;
+use super::cli::{self, Args};
+use super::models::audit::{Activity, ActivityQuery, Change, Entity};
+use super::models::completion::Completion;
+use super::models::household::{Household, HouseholdForm};
+use super::models::label::{DEFAULT_COLOR, Label};
+use super::models::schedule::{DueStatus, IntervalUnit};
+use super::models::stats::Stats;
 use super::models::task::Task;
+use super::models::undo::Undo;
+use super::models::user::{Credentials, User};
+use super::models::webhook::{Delivery, Webhook};
+use super::tui::{App, Mode, terminal_color};
+
+use std::collections::HashMap;
 
 use parking_lot::{Mutex, const_mutex};
 use rand::distr::{Alphanumeric, SampleString};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::Color;
+
+use diesel::RunQueryDsl;
 
-use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
-use rocket::http::{ContentType, Status};
+use chrono::{Datelike, Duration, Local, NaiveDate, SubsecRound};
+use clap::Parser;
+use rocket::http::{ContentType, Header, Status};
 use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
 use scraper::{Html, Selector};
 
 static DB_LOCK: Mutex<()> = const_mutex(());
@@ -24,6 +46,10 @@ macro_rules! run_test {
                 .expect("Rocket client");
             let db = super::DbConn::get_one(&$client.rocket()).await;
             let $conn = db.expect("failed to get database connection for testing");
+            assert!(
+                Webhook::delete_all(&$conn).await,
+                "failed to delete all webhooks for testing"
+            );
             assert!(
                 Task::delete_all(&$conn).await,
                 "failed to delete all tasks for testing"
@@ -32,6 +58,15 @@ macro_rules! run_test {
                 Label::delete_all(&$conn).await,
                 "failed to delete all labels for testing"
             );
+            assert!(
+                User::delete_all(&$conn).await,
+                "failed to delete all users for testing"
+            );
+            assert!(
+                Household::delete_all(&$conn).await,
+                "failed to delete all households for testing"
+            );
+            log_in_as_new_user(&$client, &$conn).await;
 
             $block
         })
@@ -40,6 +75,38 @@ macro_rules! run_test {
 
 // --- Test helpers ---
 
+const TEST_USER: &str = "tester";
+const TEST_PASSWORD: &str = "correct horse";
+
+async fn log_in_as_new_user(client: &Client, conn: &super::DbConn) {
+    let credentials = Credentials {
+        name: TEST_USER.to_string(),
+        password: TEST_PASSWORD.to_string(),
+    };
+    assert!(User::insert(credentials, conn).await.is_some());
+    let res = log_in_by_post(client, TEST_USER, TEST_PASSWORD).await;
+    assert_eq!(res.status(), Status::SeeOther);
+}
+
+/// Returns the household of the test user.
+async fn household_id(conn: &super::DbConn) -> i32 {
+    let user_id = conn
+        .run(|c| User::id_by_name(TEST_USER, c))
+        .await
+        .unwrap()
+        .expect("test user exists");
+    Household::for_user(user_id, conn).await[0].id
+}
+
+async fn log_in_by_post<'a>(client: &'a Client, name: &str, password: &str) -> LocalResponse<'a> {
+    client
+        .post("/login")
+        .header(ContentType::Form)
+        .body(format!("name={name}&password={password}"))
+        .dispatch()
+        .await
+}
+
 async fn insert_label_by_post<'a>(
     client: &'a Client,
     name: &'a str,
@@ -58,11 +125,11 @@ async fn insert_task_by_post<'a>(
     name: &'a str,
     description: &'a str,
     updated_at: &'a str,
-    label_id: Option<i32>,
+    label_ids: &[i32],
 ) -> LocalResponse<'a> {
     let mut form = format!("name={name}&description={description}&updated_at={updated_at}");
-    if let Some(id) = label_id {
-        form.push_str(&format!("&label_id={id}"));
+    for id in label_ids {
+        form.push_str(&format!("&label_ids={id}"));
     }
     client
         .post("/")
@@ -72,17 +139,53 @@ async fn insert_task_by_post<'a>(
         .await
 }
 
+/// Posts the edit form of the task, correcting the date of its latest
+/// completion to `updated_at`.
 async fn update_task_by_post<'a>(
     client: &'a Client,
     task_id: i32,
     name: &'a str,
     description: &'a str,
     updated_at: &'a str,
-    label_id: Option<i32>,
+    label_ids: &[i32],
 ) -> LocalResponse<'a> {
-    let mut form = format!("name={name}&description={description}&updated_at={updated_at}");
-    if let Some(id) = label_id {
-        form.push_str(&format!("&label_id={id}"));
+    post_task_form(
+        client,
+        task_id,
+        name,
+        description,
+        updated_at,
+        label_ids,
+        true,
+    )
+    .await
+}
+
+/// Posts the edit form of the task, recording that it was done on `done_at`.
+async fn complete_task_by_post<'a>(
+    client: &'a Client,
+    task_id: i32,
+    name: &'a str,
+    done_at: &'a str,
+    label_ids: &[i32],
+) -> LocalResponse<'a> {
+    post_task_form(client, task_id, name, "", done_at, label_ids, false).await
+}
+
+async fn post_task_form<'a>(
+    client: &'a Client,
+    task_id: i32,
+    name: &'a str,
+    description: &'a str,
+    updated_at: &'a str,
+    label_ids: &[i32],
+    correct_latest: bool,
+) -> LocalResponse<'a> {
+    let mut form = format!(
+        "name={name}&description={description}&updated_at={updated_at}&correct_latest={correct_latest}"
+    );
+    for id in label_ids {
+        form.push_str(&format!("&label_ids={id}"));
     }
     client
         .post(format!("/{task_id}"))
@@ -157,7 +260,7 @@ fn index_shows_main_task_table_headers() {
 fn index_shows_update_to_today_button() {
     run_test!(|client, _conn| {
         // --- Arrange: Insert a new task and check index page is accessible ---
-        insert_task_by_post(&client, "test+task", "", "", None).await;
+        insert_task_by_post(&client, "test+task", "", "", &[]).await;
         let res = client.get("/").dispatch().await;
         assert_eq!(res.status(), Status::Ok);
 
@@ -224,8 +327,10 @@ fn task_detail_page_shows_required_fields() {
         let task_name = "detailpagetest";
         let task_description = "desc";
         let updated_at = Local::now().naive_local().to_string();
-        insert_task_by_post(&client, task_name, task_description, &updated_at, None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, task_name, task_description, &updated_at, &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access detail page.
         let res = client.get(format!("/{inserted_id}")).dispatch().await;
@@ -252,8 +357,10 @@ fn task_detail_page_shows_update_button() {
     run_test!(|client, conn| {
         // Create new task and get its ID.
         let task_name: String = "detailpagetest".to_string();
-        insert_task_by_post(&client, &task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, &task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access detail page.
         let res = client.get(format!("/{inserted_id}")).dispatch().await;
@@ -281,8 +388,10 @@ fn task_detail_page_shows_back_button() {
     run_test!(|client, conn| {
         // Create new task and get its ID.
         let task_name: String = "detailpagetest".to_string();
-        insert_task_by_post(&client, &task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, &task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access detail page.
         let res = client.get(format!("/{inserted_id}")).dispatch().await;
@@ -318,15 +427,22 @@ fn tasks_filtered_by_label_are_displayed_correctly() {
         let mut task_ids: Vec<i32> = Vec::with_capacity(3);
         for _ in 0..3 {
             let rng_name: String = Alphanumeric.sample_string(&mut rng, 7);
-            insert_task_by_post(&client, &rng_name, "", "", None).await;
-            let inserted_id = Task::all(&conn).await.last().unwrap().id.unwrap();
+            insert_task_by_post(&client, &rng_name, "", "", &[]).await;
+            let inserted_id = Task::all(household_id(&conn).await, &conn)
+                .await
+                .last()
+                .unwrap()
+                .id
+                .unwrap();
             task_names.push(rng_name);
             task_ids.push(inserted_id);
         }
 
         // Create a new label, too.
         insert_label_by_post(&client, "newlabel", "#eeeeee").await;
-        let inserted_label_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_label_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Attach label to several tasks.
         let dt = Local::now().naive_local().to_string();
@@ -337,7 +453,7 @@ fn tasks_filtered_by_label_are_displayed_correctly() {
                 &task_names[i],
                 "",
                 &dt,
-                Some(inserted_label_id),
+                &[inserted_label_id],
             )
             .await;
         }
@@ -356,13 +472,69 @@ fn tasks_filtered_by_label_are_displayed_correctly() {
     })
 }
 
+#[test]
+fn task_with_several_labels_is_listed_under_each_label() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task with two labels ---
+        insert_label_by_post(&client, "kitchen", "#eeeeee").await;
+        insert_label_by_post(&client, "weekly", "#dddddd").await;
+        let label_ids: Vec<i32> = Label::all(household_id(&conn).await, &conn)
+            .await
+            .iter()
+            .filter_map(|l| l.id)
+            .collect();
+        insert_task_by_post(&client, "multilabel", "", "", &[]).await;
+        let task_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        let dt = Local::now().naive_local().to_string();
+        update_task_by_post(&client, task_id, "multilabel", "", &dt, &label_ids).await;
+
+        // --- Act & Assert: Both label pages list the task ---
+        for label_id in &label_ids {
+            let body = client
+                .get(format!("/label/{label_id}"))
+                .dispatch()
+                .await
+                .into_string()
+                .await
+                .unwrap();
+            assert!(body.contains("multilabel"));
+        }
+        let body = client
+            .get(format!("/{task_id}"))
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        let document = Html::parse_document(&body);
+        let selected = Selector::parse("select[name=label_ids] option[selected]").unwrap();
+        assert_eq!(document.select(&selected).count(), 2);
+
+        // --- Act: Delete one of the labels ---
+        client
+            .delete(format!("/label/{}", label_ids[0]))
+            .dispatch()
+            .await;
+
+        // --- Assert: The task keeps the other label ---
+        let task = Task::summary_by_id(task_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.label_ids, vec![label_ids[1]]);
+    })
+}
+
 #[test]
 fn task_delete_confirm_page_shows_delete_button() {
     run_test!(|client, conn| {
         // Create new task and get its ID.
         let task_name: String = "confirmpagetest".to_string();
-        insert_task_by_post(&client, &task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, &task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -387,8 +559,10 @@ fn task_delete_confirm_page_shows_back_to_task_button() {
     run_test!(|client, conn| {
         // Create new task and get its ID.
         let task_name: String = "confirmpagetest".to_string();
-        insert_task_by_post(&client, &task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, &task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -412,8 +586,10 @@ fn task_delete_confirm_page_shows_back_to_index_button() {
     run_test!(|client, conn| {
         // Create new task and get its ID.
         let task_name: String = "confirmpagetest".to_string();
-        insert_task_by_post(&client, &task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, &task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -441,7 +617,9 @@ fn label_delete_confirm_page_shows_delete_button() {
     run_test!(|client, conn| {
         // Create a new label.
         insert_label_by_post(&client, "labelconfirmtest", "#ababab").await;
-        let inserted_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -466,7 +644,9 @@ fn label_delete_confirm_page_shows_back_to_label_button() {
     run_test!(|client, conn| {
         // Create a new label.
         insert_label_by_post(&client, "labelconfirmtest", "#ababab").await;
-        let inserted_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -490,7 +670,9 @@ fn label_delete_confirm_page_shows_back_to_index_button() {
     run_test!(|client, conn| {
         // Create a new label.
         insert_label_by_post(&client, "labelconfirmtest", "#ababab").await;
-        let inserted_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // Ensure we can access confirm page.
         let res = client
@@ -517,35 +699,33 @@ fn label_delete_confirm_page_shows_back_to_index_button() {
 fn task_insertion_and_deletion_updates_db_and_ui() {
     run_test!(|client, conn| {
         // --- Arrange: Get initial tasks ---
-        let init_tasks = Task::all(&conn).await;
+        let init_tasks = Task::all(household_id(&conn).await, &conn).await;
 
         // --- Act: Insert new task ---
-        insert_task_by_post(&client, "test task", "", "", None).await;
+        insert_task_by_post(&client, "test task", "", "", &[]).await;
         let time_posted_ndt = Local::now().naive_local();
 
         // --- Assert: Task inserted in DB ---
-        let new_tasks = Task::all(&conn).await;
+        let new_tasks = Task::all(household_id(&conn).await, &conn).await;
         assert_eq!(new_tasks.len(), init_tasks.len() + 1);
         assert_eq!(new_tasks[0].name, "test task");
         assert_eq!(new_tasks[0].description, "");
-        assert!(
-            time_posted_ndt
-                - NaiveDateTime::parse_from_str(&new_tasks[0].updated_at, "%Y-%m-%d %H:%M:%S%.f")
-                    .unwrap()
-                < Duration::seconds(5)
-        );
-        assert_eq!(new_tasks[0].label_id, None);
+        assert!(time_posted_ndt - new_tasks[0].updated_at < Duration::seconds(5));
 
         // --- Act: Delete the task ---
         let id = new_tasks[0].id.unwrap();
         client.delete(format!("/{id}")).dispatch().await;
 
         // --- Assert: Task deleted from DB ---
-        let final_tasks = Task::all(&conn).await;
+        let final_tasks = Task::all(household_id(&conn).await, &conn).await;
         assert_eq!(final_tasks.len(), init_tasks.len());
         if !final_tasks.is_empty() {
             assert_ne!(final_tasks[0].name, "test task");
         }
+
+        // --- Act & Assert: A missing task isn't found ---
+        let res = client.delete(format!("/{id}")).dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
     })
 }
 
@@ -553,13 +733,13 @@ fn task_insertion_and_deletion_updates_db_and_ui() {
 fn label_insertion_and_deletion_updates_db_and_ui() {
     run_test!(|client, conn| {
         // --- Arrange: Get initial labels ---
-        let init_labels = Label::all(&conn).await;
+        let init_labels = Label::all(household_id(&conn).await, &conn).await;
 
         // --- Act: Insert new label ---
         insert_label_by_post(&client, "test label", "#ababab").await;
 
         // --- Assert: Label inserted in DB ---
-        let new_labels = Label::all(&conn).await;
+        let new_labels = Label::all(household_id(&conn).await, &conn).await;
         assert_eq!(new_labels.len(), init_labels.len() + 1);
         assert_eq!(new_labels[0].name, "test label");
         assert_eq!(new_labels[0].color_hex, "#ababab");
@@ -569,11 +749,15 @@ fn label_insertion_and_deletion_updates_db_and_ui() {
         client.delete(format!("/label/{id}")).dispatch().await;
 
         // --- Assert: Label deleted from DB ---
-        let final_labels = Label::all(&conn).await;
+        let final_labels = Label::all(household_id(&conn).await, &conn).await;
         assert_eq!(final_labels.len(), init_labels.len());
         if !final_labels.is_empty() {
             assert_ne!(final_labels[0].name, "test label");
         }
+
+        // --- Act & Assert: A missing label isn't found ---
+        let res = client.delete(format!("/label/{id}")).dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
     })
 }
 
@@ -589,7 +773,7 @@ fn inserting_multiple_tasks_preserves_insertion_order() {
         // --- Act: Insert many tasks ---
         for _ in 0..TEST_RECORD_NUM {
             let name: String = Alphanumeric.sample_string(&mut rng, 6);
-            insert_task_by_post(&client, &name, "", "", None).await;
+            insert_task_by_post(&client, &name, "", "", &[]).await;
             inserted_names.push(name);
         }
         let tasks = Task::all_by_id(&conn).await;
@@ -619,7 +803,7 @@ fn task_form_submission_without_form_shows_warnings() {
 #[test]
 fn task_form_submission_with_empty_fields_shows_warnings() {
     run_test!(|client, _conn| {
-        let res = insert_task_by_post(&client, "", "", "", None).await;
+        let res = insert_task_by_post(&client, "", "", "", &[]).await;
 
         let mut cookies = res.headers().get("Set-Cookie");
         assert_eq!(res.status(), Status::SeeOther);
@@ -630,7 +814,7 @@ fn task_form_submission_with_empty_fields_shows_warnings() {
 #[test]
 fn task_form_submission_with_empty_name_shows_warnings() {
     run_test!(|client, _conn| {
-        let res = insert_task_by_post(&client, "", "description", "", None).await;
+        let res = insert_task_by_post(&client, "", "description", "", &[]).await;
 
         let mut cookies = res.headers().get("Set-Cookie");
         assert_eq!(res.status(), Status::SeeOther);
@@ -745,8 +929,10 @@ fn task_update_form_submission_without_form_shows_warnings() {
     run_test!(|client, conn| {
         // Arrange: Insert a task and get its ID
         let task_name = "detailformtest";
-        insert_task_by_post(&client, task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
         let post_url = format!("/{inserted_id}");
 
         // Act: POST with no form data
@@ -767,8 +953,10 @@ fn task_update_form_submission_without_form_shows_warnings() {
 fn task_update_form_submission_without_name_shows_warnings() {
     run_test!(|client, conn| {
         let task_name = "detailformtest";
-        insert_task_by_post(&client, task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
         let post_url = format!("/{inserted_id}");
 
         // Act: POST without name field
@@ -789,8 +977,10 @@ fn task_update_form_submission_without_name_shows_warnings() {
 fn task_update_form_submission_without_description_shows_warnings() {
     run_test!(|client, conn| {
         let task_name = "detailformtest";
-        insert_task_by_post(&client, task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
         let post_url = format!("/{inserted_id}");
 
         // Act: POST without description field
@@ -811,8 +1001,10 @@ fn task_update_form_submission_without_description_shows_warnings() {
 fn task_update_form_submission_with_empty_name_shows_warnings() {
     run_test!(|client, conn| {
         let task_name = "detailformtest";
-        insert_task_by_post(&client, task_name, "", "", None).await;
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
+        insert_task_by_post(&client, task_name, "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
         let post_url = format!("/{inserted_id}");
 
         // Act: POST with empty name
@@ -836,26 +1028,22 @@ fn updating_task_date_sets_to_today() {
         let mut rng = rand::rng();
         let rng_name: String = Alphanumeric.sample_string(&mut rng, 7);
 
-        let t = Task::insert_with_old_date(&rng_name, &conn).await;
+        let t = Task::insert_with_old_date(&rng_name, household_id(&conn).await, &conn).await;
         assert!(t);
 
         // Ensure `updated_at` of created task is updated to today.
-        let new_tasks = Task::all(&conn).await;
+        let new_tasks = Task::all(household_id(&conn).await, &conn).await;
         let today_ndt = Local::now().naive_local();
         // First, ensure current task date is not today.
-        let new_task_nd = NaiveDate::parse_from_str(&new_tasks[0].updated_at, "%Y-%m-%d").unwrap();
-        assert_ne!(new_task_nd, today_ndt.date());
+        assert_ne!(new_tasks[0].updated_at.date(), today_ndt.date());
 
         let inserted_id = new_tasks[0].id.unwrap(); // `id` is `Nullable`
         let res = client.post(format!("/{inserted_id}/date")).dispatch().await;
         let mut cookies = res.headers().get("Set-Cookie");
-        let final_tasks = Task::all(&conn).await;
-        let final_task_ndt =
-            NaiveDateTime::parse_from_str(&final_tasks[0].updated_at, "%Y-%m-%d %H:%M:%S%.f")
-                .unwrap();
+        let final_tasks = Task::all(household_id(&conn).await, &conn).await;
         assert_eq!(res.status(), Status::SeeOther);
         assert!(cookies.any(|value| value.contains("success")));
-        assert_eq!(final_task_ndt.date(), today_ndt.date());
+        assert_eq!(final_tasks[0].updated_at.date(), today_ndt.date());
     })
 }
 
@@ -864,23 +1052,29 @@ fn updating_task_fields_persists_changes() {
     run_test!(|client, conn| {
         // --- Arrange: Create new task and label ---
         let task_name = "updatetasktest".to_string();
-        let t = Task::insert_with_old_date(&task_name, &conn).await;
+        let t = Task::insert_with_old_date(&task_name, household_id(&conn).await, &conn).await;
         assert!(t);
 
         insert_label_by_post(&client, "newlabel", "#eeeeee").await;
 
         // --- Act: Submit valid update form ---
-        let inserted_id = Task::all(&conn).await[0].id.unwrap();
-        let inserted_label_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        let inserted_label_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
         let task_description = "newdescription".to_string();
-        let dt = Local::now().naive_local().to_string();
+        // PostgreSQL keeps microseconds.
+        let now = Local::now().naive_local().trunc_subsecs(6);
+        let dt = now.to_string();
         let res = update_task_by_post(
             &client,
             inserted_id,
             &task_name,
             &task_description,
             &dt,
-            Some(inserted_label_id),
+            &[inserted_label_id],
         )
         .await;
 
@@ -889,28 +1083,32 @@ fn updating_task_fields_persists_changes() {
         assert_eq!(res.status(), Status::SeeOther);
         assert!(cookies.any(|value| value.contains("success")));
 
-        let updated_task = Task::task_by_id(inserted_id, &conn).await;
-        assert_eq!(updated_task.name, task_name);
-        assert_eq!(updated_task.description, task_description);
-        assert_eq!(updated_task.updated_at, dt);
-        assert_eq!(updated_task.label_id, Some(inserted_label_id));
+        let updated_task = Task::summary_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(updated_task.task.name, task_name);
+        assert_eq!(updated_task.task.description, task_description);
+        assert_eq!(updated_task.task.updated_at, now);
+        assert_eq!(updated_task.label_ids, vec![inserted_label_id]);
 
-        // Update label_id to NULL.
+        // Remove all labels.
         let res = update_task_by_post(
             &client,
             inserted_id,
             &task_name,
             &task_description,
             &dt,
-            None,
+            &[],
         )
         .await;
         let mut cookies = res.headers().get("Set-Cookie");
         assert_eq!(res.status(), Status::SeeOther);
         assert!(cookies.any(|value| value.contains("success")));
 
-        let updated_task = Task::task_by_id(inserted_id, &conn).await;
-        assert_eq!(updated_task.label_id, None);
+        let updated_task = Task::summary_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert!(updated_task.label_ids.is_empty());
     })
 }
 
@@ -919,7 +1117,9 @@ fn updating_label_fields_persists_changes() {
     run_test!(|client, conn| {
         // --- Arrange: Create a new label ---
         insert_label_by_post(&client, "newlabel", "#eeeeee").await;
-        let inserted_id = Label::all(&conn).await[0].id.unwrap();
+        let inserted_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
 
         // --- Act: Update the label ---
         let new_name = "newnewlabel".to_string();
@@ -931,8 +1131,2486 @@ fn updating_label_fields_persists_changes() {
         assert_eq!(res.status(), Status::SeeOther);
         assert!(cookies.any(|value| value.contains("success")));
 
-        let updated_label = Label::label_by_id(inserted_id, &conn).await;
+        let updated_label = Label::label_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
         assert_eq!(updated_label.name, new_name);
         assert_eq!(updated_label.color_hex, new_color);
     })
 }
+
+#[test]
+fn marking_task_done_keeps_previous_dates_in_history() {
+    run_test!(|client, conn| {
+        // --- Arrange: Create a task, which is done once on insertion ---
+        insert_task_by_post(&client, "historytest", "", "", &[]).await;
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        let first_date = Task::task_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap()
+            .updated_at;
+
+        // --- Act: Mark it done twice more ---
+        client.post(format!("/{inserted_id}/date")).dispatch().await;
+        client.post(format!("/{inserted_id}/date")).dispatch().await;
+
+        // --- Assert: All completions are kept and `updated_at` is the latest ---
+        let completions = Completion::for_task(inserted_id, &conn).await;
+        assert_eq!(completions.len(), 3);
+        assert_eq!(completions[2].completed_at, first_date);
+        let task = Task::task_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.updated_at, completions[0].completed_at);
+    })
+}
+
+#[test]
+fn updating_task_date_records_completion_with_note() {
+    run_test!(|client, conn| {
+        // --- Arrange: Create a task with old date ---
+        assert!(
+            Task::insert_with_old_date("historynotetest", household_id(&conn).await, &conn).await
+        );
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+
+        // --- Act: Update its date with a note ---
+        let res = client
+            .post(format!("/{inserted_id}"))
+            .header(ContentType::Form)
+            .body("name=historynotetest&description=&updated_at=2020-04-28&note=spring+cleaning")
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+
+        // --- Assert: Completion is recorded and shown in detail page ---
+        let completions = Completion::for_task(inserted_id, &conn).await;
+        assert_eq!(completions.len(), 1);
+        assert_eq!(
+            completions[0].completed_at,
+            NaiveDate::from_ymd_opt(2020, 4, 28)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(completions[0].note.as_deref(), Some("spring cleaning"));
+
+        let res = client.get(format!("/{inserted_id}")).dispatch().await;
+        let body = res.into_string().await.unwrap();
+        assert!(body.contains("History"));
+        assert!(body.contains("spring cleaning"));
+
+        // --- Act: Save an earlier date with another note ---
+        client
+            .post(format!("/{inserted_id}"))
+            .header(ContentType::Form)
+            .body("name=historynotetest&description=&updated_at=2020-04-01&note=missed+one")
+            .dispatch()
+            .await;
+
+        // --- Assert: It's a past completion, and the latest one is kept ---
+        let completions = Completion::for_task(inserted_id, &conn).await;
+        let dates: Vec<_> = completions
+            .iter()
+            .map(|c| (c.completed_at.date().to_string(), c.note.as_deref()))
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                ("2020-04-28".to_string(), Some("spring cleaning")),
+                ("2020-04-01".to_string(), Some("missed one")),
+            ]
+        );
+        let task = Task::task_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.updated_at, completions[0].completed_at);
+
+        // --- Act: Correct the date of the latest completion ---
+        client
+            .post(format!("/{inserted_id}"))
+            .header(ContentType::Form)
+            .body("name=historynotetest&description=&updated_at=2020-04-20&correct_latest=true")
+            .dispatch()
+            .await;
+
+        // --- Assert: It's moved with its note ---
+        let completions = Completion::for_task(inserted_id, &conn).await;
+        assert_eq!(completions.len(), 2);
+        assert_eq!(completions[0].completed_at.date().to_string(), "2020-04-20");
+        assert_eq!(completions[0].note.as_deref(), Some("spring cleaning"));
+    })
+}
+
+#[test]
+fn task_interval_gives_next_due_date_and_status() {
+    run_test!(|client, conn| {
+        // --- Arrange: Create a task done in 2000 ---
+        assert!(Task::insert_with_old_date("intervaltest", household_id(&conn).await, &conn).await);
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+
+        // --- Act: Set interval of 2 weeks ---
+        let res = client
+            .post(format!("/{inserted_id}"))
+            .header(ContentType::Form)
+            .body("name=intervaltest&description=&updated_at=2000-01-01&interval_count=2&interval_unit=weeks")
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+
+        // --- Assert: The task is overdue since 2000-01-15 ---
+        let task = Task::summary_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.task.interval_count, Some(2));
+        assert_eq!(task.task.interval_unit, Some(IntervalUnit::Weeks));
+        assert_eq!(task.next_due, NaiveDate::from_ymd_opt(2000, 1, 15));
+        assert_eq!(task.status, Some(DueStatus::Overdue));
+
+        let body = client
+            .get("/")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(body.contains("2000-01-15 (overdue)"));
+    })
+}
+
+#[test]
+fn index_lists_overdue_tasks_first() {
+    run_test!(|client, conn| {
+        // --- Arrange: An old task without interval and a new overdue one ---
+        assert!(Task::insert_with_old_date("nointerval", household_id(&conn).await, &conn).await);
+        insert_task_by_post(&client, "withinterval", "", "", &[]).await;
+        let scheduled_id = Task::all(household_id(&conn).await, &conn).await[1]
+            .id
+            .unwrap();
+        let yesterday = (Local::now() - Duration::days(2)).date_naive();
+        client
+            .post(format!("/{scheduled_id}"))
+            .header(ContentType::Form)
+            .body(format!(
+                "name=withinterval&description=&updated_at={yesterday}&correct_latest=true&interval_count=1&interval_unit=days"
+            ))
+            .dispatch()
+            .await;
+
+        // --- Act: Sort tasks by due ---
+        let tasks = Task::all_by_due(household_id(&conn).await, &conn).await;
+
+        // --- Assert: Overdue task comes before the older one without interval ---
+        assert_eq!(tasks[0].task.name, "withinterval");
+        assert_eq!(tasks[0].status, Some(DueStatus::Overdue));
+        assert_eq!(tasks[1].task.name, "nointerval");
+        assert_eq!(tasks[1].status, None);
+    })
+}
+
+#[test]
+fn task_update_form_submission_with_partial_interval_shows_warnings() {
+    run_test!(|client, conn| {
+        assert!(
+            Task::insert_with_old_date("intervalwarningtest", household_id(&conn).await, &conn)
+                .await
+        );
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+
+        for interval in [
+            "interval_count=2",
+            "interval_unit=days",
+            "interval_count=0&interval_unit=days",
+        ] {
+            let res = client
+                .post(format!("/{inserted_id}"))
+                .header(ContentType::Form)
+                .body(format!(
+                    "name=foo&description=&updated_at=2000-01-01&{interval}"
+                ))
+                .dispatch()
+                .await;
+            let mut cookies = res.headers().get("Set-Cookie");
+            assert_eq!(res.status(), Status::SeeOther);
+            assert!(cookies.any(|value| value.contains("warning")));
+        }
+        assert_eq!(
+            Task::task_by_id(inserted_id, household_id(&conn).await, &conn)
+                .await
+                .unwrap()
+                .interval_count,
+            None
+        );
+    })
+}
+
+#[test]
+fn api_creates_and_gets_task() {
+    run_test!(|client, _conn| {
+        // --- Act: Create a task via API ---
+        let res = client
+            .post("/api/v1/tasks")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "apitask"}"#)
+            .dispatch()
+            .await;
+
+        // --- Assert: Created task is returned and can be fetched ---
+        assert_eq!(res.status(), Status::Created);
+        let location = res.headers().get_one("Location").unwrap().to_string();
+        let created: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(created["name"], "apitask");
+        assert_eq!(location, format!("/api/v1/tasks/{}", created["id"]));
+
+        let res = client.get(location).dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+        let fetched: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(fetched, created);
+
+        let res = client.get("/api/v1/tasks").dispatch().await;
+        let tasks: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(tasks.as_array().unwrap().len(), 1);
+    })
+}
+
+#[test]
+fn api_rejects_invalid_task_with_unprocessable_entity() {
+    run_test!(|client, conn| {
+        let res = client
+            .post("/api/v1/tasks")
+            .header(ContentType::JSON)
+            .body(r#"{"name": ""}"#)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::UnprocessableEntity);
+        let body: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(body["errors"][0]["field"], "name");
+
+        // Broken JSON is rejected with JSON, too.
+        let res = client
+            .post("/api/v1/tasks")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "#)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::BadRequest);
+        assert_eq!(res.content_type(), Some(ContentType::JSON));
+
+        // So is JSON without required fields.
+        let res = client
+            .post("/api/v1/tasks")
+            .header(ContentType::JSON)
+            .body(r#"{"description": "no name"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::UnprocessableEntity);
+        assert_eq!(res.content_type(), Some(ContentType::JSON));
+
+        assert!(Task::all(household_id(&conn).await, &conn).await.is_empty());
+    })
+}
+
+#[test]
+fn api_returns_not_found_for_unknown_task() {
+    run_test!(|client, _conn| {
+        let res = client.get("/api/v1/tasks/9999").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+        assert_eq!(res.content_type(), Some(ContentType::JSON));
+
+        let res = client.delete("/api/v1/tasks/9999").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+
+        let res = client.post("/api/v1/tasks/9999/done").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+    })
+}
+
+#[test]
+fn api_updates_marks_done_and_deletes_task() {
+    run_test!(|client, conn| {
+        // --- Arrange: Create a task with old date ---
+        assert!(Task::insert_with_old_date("apiupdate", household_id(&conn).await, &conn).await);
+        let id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+
+        // --- Act & Assert: Update it ---
+        let res = client
+            .put(format!("/api/v1/tasks/{id}"))
+            .header(ContentType::JSON)
+            .body(r#"{"name": "renamed", "description": "desc", "interval_count": 1, "interval_unit": "months"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        let task: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(task["name"], "renamed");
+        assert_eq!(task["updated_at"], "2000-01-01T00:00:00");
+        assert_eq!(task["next_due"], "2000-02-01");
+        assert_eq!(task["status"], "overdue");
+
+        // --- Act & Assert: Mark it done with a note ---
+        let res = client
+            .post(format!("/api/v1/tasks/{id}/done"))
+            .header(ContentType::JSON)
+            .body(r#"{"note": "via api"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        let task: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(task["status"], "ok");
+        let completions = Completion::for_task(id, &conn).await;
+        assert_eq!(completions[0].note.as_deref(), Some("via api"));
+
+        // --- Act & Assert: Delete it ---
+        let res = client
+            .delete(format!("/api/v1/tasks/{id}"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NoContent);
+        assert!(Task::all(household_id(&conn).await, &conn).await.is_empty());
+    })
+}
+
+#[test]
+fn api_creates_updates_and_deletes_label() {
+    run_test!(|client, conn| {
+        // --- Act & Assert: Create a label ---
+        let res = client
+            .post("/api/v1/labels")
+            .header(ContentType::JSON)
+            .body(r##"{"name": "apilabel", "color": "#aabbcc"}"##)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Created);
+        let label: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(label["name"], "apilabel");
+        let id = label["id"].as_i64().unwrap();
+
+        // --- Act & Assert: Update it ---
+        let res = client
+            .put(format!("/api/v1/labels/{id}"))
+            .header(ContentType::JSON)
+            .body(r##"{"name": "renamed", "color": "#112233"}"##)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        let res = client.get(format!("/api/v1/labels/{id}")).dispatch().await;
+        let label: serde_json::Value = res.into_json().await.unwrap();
+        assert_eq!(label["name"], "renamed");
+        assert_eq!(label["color_hex"], "#112233");
+
+        // --- Act & Assert: Delete it ---
+        let res = client
+            .delete(format!("/api/v1/labels/{id}"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NoContent);
+        assert!(
+            Label::all(household_id(&conn).await, &conn)
+                .await
+                .is_empty()
+        );
+        let res = client.get(format!("/api/v1/labels/{id}")).dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+    })
+}
+
+#[test]
+fn api_rejects_invalid_label_colors_with_field_errors() {
+    run_test!(|client, conn| {
+        for color in ["red", "#1234567", "#12345", "x#123456", ""] {
+            let res = client
+                .post("/api/v1/labels")
+                .header(ContentType::JSON)
+                .body(format!(r#"{{"name": "", "color": "{color}"}}"#))
+                .dispatch()
+                .await;
+            assert_eq!(res.status(), Status::UnprocessableEntity);
+            let body: serde_json::Value = res.into_json().await.unwrap();
+            assert_eq!(body["errors"][0]["field"], "name");
+            assert_eq!(body["errors"][1]["field"], "color");
+        }
+        assert!(
+            Label::all(household_id(&conn).await, &conn)
+                .await
+                .is_empty()
+        );
+    })
+}
+
+#[test]
+fn api_lists_tasks_of_label() {
+    run_test!(|client, conn| {
+        // --- Arrange: Two tasks, one of them labeled ---
+        insert_label_by_post(&client, "apilabel", "#eeeeee").await;
+        let label_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        insert_task_by_post(&client, "labeled", "", "", &[]).await;
+        insert_task_by_post(&client, "unlabeled", "", "", &[]).await;
+        let task_id = Task::all_by_id(&conn).await[0].id.unwrap();
+        let dt = Local::now().naive_local().to_string();
+        update_task_by_post(&client, task_id, "labeled", "", &dt, &[label_id]).await;
+
+        // --- Act: Get tasks of the label ---
+        let res = client
+            .get(format!("/api/v1/labels/{label_id}/tasks"))
+            .dispatch()
+            .await;
+
+        // --- Assert: Only the labeled task is listed ---
+        assert_eq!(res.status(), Status::Ok);
+        let tasks: serde_json::Value = res.into_json().await.unwrap();
+        let tasks = tasks.as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["name"], "labeled");
+
+        let res = client.get("/api/v1/labels/9999/tasks").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+    })
+}
+
+#[test]
+fn unknown_task_and_label_pages_return_not_found() {
+    run_test!(|client, _conn| {
+        let urls = [
+            "/9999",
+            "/9999/confirm",
+            "/label/9999",
+            "/label/9999/edit",
+            "/label/9999/confirm",
+            "/no/such/page",
+        ];
+        for url in urls {
+            let res = client.get(url).dispatch().await;
+            assert_eq!(res.status(), Status::NotFound, "{url} is found");
+            let body = res.into_string().await.unwrap();
+            assert!(body.contains("doesn't exist"), "{url} shows no 404 page");
+        }
+        for url in ["/9999/date", "/9999/archive", "/9999/unarchive"] {
+            let res = client.post(url).dispatch().await;
+            assert_eq!(res.status(), Status::NotFound, "{url} is found");
+        }
+    })
+}
+
+#[test]
+fn task_update_form_submission_with_invalid_date_shows_warnings() {
+    run_test!(|client, conn| {
+        assert!(
+            Task::insert_with_old_date("datewarningtest", household_id(&conn).await, &conn).await
+        );
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        let tomorrow = (Local::now() + Duration::days(1)).date_naive();
+
+        for date in [
+            "garbage".to_string(),
+            "2020-13-01".to_string(),
+            tomorrow.to_string(),
+        ] {
+            let res = update_task_by_post(&client, inserted_id, "foo", "", &date, &[]).await;
+            let mut cookies = res.headers().get("Set-Cookie");
+            assert_eq!(res.status(), Status::SeeOther);
+            assert!(
+                cookies.any(|value| value.contains("warning")),
+                "{date} is accepted"
+            );
+        }
+
+        let task = Task::task_by_id(inserted_id, household_id(&conn).await, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.name, "datewarningtest");
+        assert_eq!(
+            task.updated_at.date(),
+            NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+        );
+    })
+}
+
+#[test]
+fn task_detail_page_shows_date_for_date_input() {
+    run_test!(|client, conn| {
+        assert!(
+            Task::insert_with_old_date("dateinputtest", household_id(&conn).await, &conn).await
+        );
+        let inserted_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+
+        let res = client.get(format!("/{inserted_id}")).dispatch().await;
+        let body = res.into_string().await.unwrap();
+        let document = Html::parse_document(&body);
+        let input = document
+            .select(&Selector::parse("input[name='updated_at']").unwrap())
+            .next()
+            .unwrap();
+        assert_eq!(input.value().attr("value"), Some("2000-01-01"));
+    })
+}
+
+#[test]
+fn pages_and_api_require_login() {
+    run_test!(|client, conn| {
+        // --- Arrange: Log out ---
+        client.post("/logout").dispatch().await;
+
+        // --- Act & Assert: Pages redirect to the login page ---
+        for path in ["/", "/label"] {
+            let res = client.get(path).dispatch().await;
+            assert_eq!(res.status(), Status::SeeOther);
+            assert_eq!(res.headers().get_one("Location"), Some("/login"));
+        }
+        let res = client
+            .post("/")
+            .header(ContentType::Form)
+            .body("name=intruder")
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+        assert_eq!(res.headers().get_one("Location"), Some("/login"));
+
+        // --- Act & Assert: API answers 401 ---
+        let res = client.get("/api/v1/tasks").dispatch().await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        assert_eq!(res.content_type(), Some(ContentType::JSON));
+
+        // --- Assert: Nothing was created ---
+        assert!(Task::all(household_id(&conn).await, &conn).await.is_empty());
+    })
+}
+
+#[test]
+fn login_rejects_wrong_password() {
+    run_test!(|client, _conn| {
+        // --- Arrange: Log out ---
+        client.post("/logout").dispatch().await;
+
+        // --- Act: Log in with a wrong password ---
+        let res = log_in_by_post(&client, TEST_USER, "wrong password").await;
+
+        // --- Assert: Still logged out ---
+        assert_eq!(res.headers().get_one("Location"), Some("/login"));
+        let res = client.get("/").dispatch().await;
+        assert_eq!(res.headers().get_one("Location"), Some("/login"));
+
+        // --- Act: Log in with the right one ---
+        log_in_by_post(&client, TEST_USER, TEST_PASSWORD).await;
+
+        // --- Assert: Logged in ---
+        let res = client.get("/").dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+    })
+}
+
+#[test]
+fn signup_is_closed_once_a_user_exists() {
+    run_test!(|client, conn| {
+        // --- Arrange: Log out, while the test user exists ---
+        client.post("/logout").dispatch().await;
+
+        // --- Act: Try to sign up ---
+        let res = client
+            .post("/signup")
+            .header(ContentType::Form)
+            .body("name=stranger&password=password123")
+            .dispatch()
+            .await;
+
+        // --- Assert: Rejected ---
+        assert_eq!(res.headers().get_one("Location"), Some("/login"));
+        assert_eq!(User::count(&conn).await, 1);
+
+        // --- Act: Sign up as the first user of an empty app ---
+        assert!(User::delete_all(&conn).await);
+        let res = client
+            .post("/signup")
+            .header(ContentType::Form)
+            .body("name=first&password=password123")
+            .dispatch()
+            .await;
+
+        // --- Assert: The new user is logged in ---
+        assert_eq!(res.headers().get_one("Location"), Some("/"));
+        let res = client.get("/").dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+    })
+}
+
+#[test]
+fn households_keep_tasks_apart_until_shared() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task of the test user and another user ---
+        let home_id = household_id(&conn).await;
+        insert_task_by_post(&client, "mychore", "", "", &[]).await;
+        let task_id = Task::all(home_id, &conn).await[0].id.unwrap();
+        let credentials = Credentials {
+            name: "other".to_string(),
+            password: TEST_PASSWORD.to_string(),
+        };
+        assert!(User::insert(credentials, &conn).await.is_some());
+
+        // --- Act: Log in as the other user ---
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, "other", TEST_PASSWORD).await;
+
+        // --- Assert: The task is invisible and untouchable ---
+        let body = client
+            .get("/")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(!body.contains("mychore"));
+        let res = client.get(format!("/{task_id}")).dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+        let res = client.delete(format!("/{task_id}")).dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+        assert_eq!(Task::all(home_id, &conn).await.len(), 1);
+
+        // --- Act: The test user shares the household ---
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, TEST_USER, TEST_PASSWORD).await;
+        client
+            .post("/household/members")
+            .header(ContentType::Form)
+            .body("user_name=other")
+            .dispatch()
+            .await;
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, "other", TEST_PASSWORD).await;
+        client
+            .post(format!("/household/{home_id}/switch"))
+            .dispatch()
+            .await;
+
+        // --- Assert: The other user sees the task ---
+        let body = client
+            .get("/")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(body.contains("mychore"));
+    })
+}
+
+#[test]
+fn new_users_get_own_household_and_adopt_orphans_explicitly() {
+    run_test!(|client, conn| {
+        // --- Arrange: A household nobody belongs to, and a new user ---
+        conn.run(|c| diesel::sql_query("INSERT INTO households (name) VALUES ('Home')").execute(c))
+            .await
+            .unwrap();
+        let credentials = Credentials {
+            name: "other".to_string(),
+            password: TEST_PASSWORD.to_string(),
+        };
+        let other = User::insert(credentials, &conn).await.unwrap();
+        let names = |households: Vec<Household>| -> Vec<String> {
+            households.into_iter().map(|h| h.name).collect()
+        };
+        assert_eq!(
+            names(Household::for_user(other, &conn).await),
+            vec!["other's home"]
+        );
+
+        // --- Act & Assert: The user adopts it from the command line ---
+        let out = run_cli(&conn, &["adopt", "other"]).await.unwrap();
+        assert!(out.contains("Home"), "{out}");
+        assert_eq!(
+            names(Household::for_user(other, &conn).await),
+            vec!["Home", "other's home"]
+        );
+        assert!(run_cli(&conn, &["adopt", "nobody"]).await.is_err());
+
+        // --- Act & Assert: Without households, pages offer to make one ---
+        conn.run(move |c| {
+            diesel::sql_query(format!(
+                "DELETE FROM household_members WHERE user_id = {other}"
+            ))
+            .execute(c)
+        })
+        .await
+        .unwrap();
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, "other", TEST_PASSWORD).await;
+        let res = client.get("/").dispatch().await;
+        assert_eq!(res.status(), Status::Forbidden);
+        let body = res.into_string().await.unwrap();
+        assert!(body.contains("action=\"/household\""), "{body}");
+    })
+}
+
+#[test]
+fn first_user_to_sign_up_gets_households_nobody_belongs_to() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task in a household left without users, as after
+        // the households migration ---
+        insert_task_by_post(&client, "oldchore", "", "", &[]).await;
+        client.post("/logout").dispatch().await;
+        conn.run(|c| diesel::sql_query("DELETE FROM household_members").execute(c))
+            .await
+            .unwrap();
+        assert!(User::delete_all(&conn).await);
+
+        // --- Act: Sign up as the first user ---
+        client
+            .post("/signup")
+            .header(ContentType::Form)
+            .body("name=first&password=password123")
+            .dispatch()
+            .await;
+
+        // --- Assert: The task is on the index page ---
+        let body = client
+            .get("/")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(body.contains("oldchore"), "{body}");
+    })
+}
+
+#[test]
+fn calendar_feed_lists_tasks_with_due_and_labels() {
+    run_test!(|client, conn| {
+        // --- Arrange: A labeled task with interval and another one ---
+        insert_label_by_post(&client, "bathroom", "#eeeeee").await;
+        let label_id = Label::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        assert!(Task::insert_with_old_date("fan", household_id(&conn).await, &conn).await);
+        insert_task_by_post(&client, "shopping", "", "", &[]).await;
+        let fan_id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        client
+            .post(format!("/{fan_id}"))
+            .header(ContentType::Form)
+            .body(format!(
+                "name=fan&description=dust, then wash&updated_at=2000-01-01&label_ids={label_id}&interval_count=1&interval_unit=months"
+            ))
+            .dispatch()
+            .await;
+
+        // --- Act: Get the feed ---
+        let res = client.get("/calendar.ics").dispatch().await;
+
+        // --- Assert: Both tasks are there, with due date and label ---
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.content_type(), Some(ContentType::Calendar));
+        let body = res.into_string().await.unwrap();
+        assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert_eq!(body.matches("BEGIN:VTODO").count(), 2);
+        assert!(body.contains("SUMMARY:fan\r\n"));
+        assert!(body.contains("DTSTART;VALUE=DATE:20000101\r\n"));
+        assert!(body.contains("DUE;VALUE=DATE:20000201\r\n"));
+        assert!(body.contains("DESCRIPTION:dust\\, then wash\r\n"));
+        assert!(body.contains("CATEGORIES:bathroom\r\n"));
+
+        // --- Act & Assert: The label feed has only the labeled task ---
+        let body = client
+            .get(format!("/label/{label_id}/calendar.ics"))
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert_eq!(body.matches("BEGIN:VTODO").count(), 1);
+        assert!(body.contains("SUMMARY:fan\r\n"));
+    })
+}
+
+#[test]
+fn calendar_feed_needs_token_without_login() {
+    run_test!(|client, conn| {
+        // --- Arrange: Get the token made with the household, then log out ---
+        insert_task_by_post(&client, "subscribed", "", "", &[]).await;
+        let household_id = household_id(&conn).await;
+        let token = Household::calendar_token(household_id, &conn)
+            .await
+            .unwrap();
+        client.get("/household").dispatch().await;
+        assert_eq!(
+            Household::calendar_token(household_id, &conn).await,
+            Some(token.clone())
+        );
+        client.post("/logout").dispatch().await;
+
+        // --- Act & Assert: Only the right token unlocks the feed ---
+        let res = client.get("/calendar.ics").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+        let res = client.get("/calendar.ics?token=wrong").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
+        let res = client
+            .get(format!("/calendar.ics?token={token}"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        assert!(
+            res.into_string()
+                .await
+                .unwrap()
+                .contains("SUMMARY:subscribed")
+        );
+
+        // --- Act & Assert: A new link replaces the old one ---
+        log_in_by_post(&client, TEST_USER, TEST_PASSWORD).await;
+        client.post("/household/calendar").dispatch().await;
+        let new_token = Household::calendar_token(household_id, &conn)
+            .await
+            .unwrap();
+        assert_ne!(new_token, token);
+        let res = client
+            .get(format!("/calendar.ics?token={token}"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotFound);
+        let res = client
+            .get(format!("/calendar.ics?token={new_token}"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+    })
+}
+
+/// Posts a multipart form with `file` as its "file" field.
+async fn post_file<'a>(
+    client: &'a Client,
+    uri: &'a str,
+    file: &str,
+    fields: &[(&str, &str)],
+) -> LocalResponse<'a> {
+    let boundary = "X-LAST-DATE-BOUNDARY";
+    let mut body = String::new();
+    for (name, value) in fields {
+        body.push_str(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ));
+    }
+    body.push_str(&format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"upload\"\r\n\r\n{file}\r\n--{boundary}--\r\n"
+    ));
+    client
+        .post(uri)
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", boundary)))
+        .body(body)
+        .dispatch()
+        .await
+}
+
+/// Posts `csv` as the file of the import form.
+async fn import_csv_by_post(client: &Client, csv: &str) -> String {
+    let res = post_file(client, "/import", csv, &[]).await;
+    assert_eq!(res.status(), Status::Ok);
+    res.into_string().await.unwrap()
+}
+
+#[test]
+fn export_csv_has_tasks_with_labels() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task with two labels ---
+        insert_label_by_post(&client, "kitchen", "#eeeeee").await;
+        insert_label_by_post(&client, "weekly", "#eeeeee").await;
+        let label_ids: Vec<i32> = Label::all(household_id(&conn).await, &conn)
+            .await
+            .iter()
+            .map(|l| l.id.unwrap())
+            .collect();
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+        let id = Task::all(household_id(&conn).await, &conn).await[0]
+            .id
+            .unwrap();
+        update_task_by_post(&client, id, "sink", "scrub it", "2000-01-01", &label_ids).await;
+
+        // --- Act: Export ---
+        let res = client.get("/export.csv").dispatch().await;
+
+        // --- Assert: Header and the task line ---
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.content_type(), Some(ContentType::CSV));
+        let body = res.into_string().await.unwrap();
+        let mut lines = body.lines();
+        assert_eq!(
+            lines.next(),
+            Some("id,name,description,updated_at,labels,interval_count,interval_unit")
+        );
+        assert_eq!(
+            lines.next(),
+            Some(format!("{id},sink,scrub it,2000-01-01,kitchen;weekly,,").as_str())
+        );
+        assert_eq!(lines.next(), None);
+    })
+}
+
+#[test]
+fn export_csv_includes_archived_tasks() {
+    run_test!(|client, conn| {
+        // --- Arrange: An archived task and an active one ---
+        for name in ["heater", "fan"] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+        }
+        let heater = Task::all(household_id(&conn).await, &conn)
+            .await
+            .into_iter()
+            .find(|t| t.name == "heater")
+            .unwrap()
+            .id
+            .unwrap();
+        client.post(format!("/{heater}/archive")).dispatch().await;
+
+        // --- Act: Export ---
+        let body = client
+            .get("/export.csv")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+
+        // --- Assert: Both are exported ---
+        let mut names: Vec<&str> = body
+            .lines()
+            .skip(1)
+            .filter_map(|l| l.split(',').nth(1))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["fan", "heater"]);
+    })
+}
+
+#[test]
+fn import_csv_creates_updates_and_reports_rejected_rows() {
+    run_test!(|client, conn| {
+        // --- Arrange: One existing task ---
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+        let household_id = household_id(&conn).await;
+        let id = Task::all(household_id, &conn).await[0].id.unwrap();
+
+        // --- Act: Import new, updated and bad rows ---
+        let csv = format!(
+            "id,name,description,updated_at,labels,interval_count,interval_unit\n\
+             ,fan,dust it,2000-01-01,bathroom; weekly,1,months\n\
+             {id},sink,scrub it,2000-02-02,bathroom,,\n\
+             ,,,,,,\n\
+             999999,ghost,,,attic,,\n\
+             ,oven,,2000-01-01,,2,fortnights\n"
+        );
+        let body = import_csv_by_post(&client, &csv).await;
+
+        // --- Assert: Good rows are in, bad rows are reported and leave nothing ---
+        assert!(body.contains("Some rows were rejected."));
+        assert!(body.contains("created"));
+        assert!(body.contains("updated"));
+        assert!(body.contains("No task with id 999999."));
+        let tasks = Task::all_by_due(household_id, &conn).await;
+        assert_eq!(tasks.len(), 2);
+        let labels = Label::all(household_id, &conn).await;
+        assert_eq!(labels.len(), 2);
+        let bathroom = labels.iter().find(|l| l.name == "bathroom").unwrap();
+        assert_eq!(bathroom.color_hex, DEFAULT_COLOR);
+        let fan = tasks.iter().find(|t| t.task.name == "fan").unwrap();
+        assert_eq!(fan.task.description, "dust it");
+        assert_eq!(fan.label_ids.len(), 2);
+        assert_eq!(fan.task.interval_count, Some(1));
+        let sink = tasks.iter().find(|t| t.task.id == Some(id)).unwrap();
+        assert_eq!(sink.task.description, "scrub it");
+        assert_eq!(sink.task.updated_at.format("%F").to_string(), "2000-02-02");
+        assert_eq!(sink.label_ids, vec![bathroom.id.unwrap()]);
+    })
+}
+
+/// Posts `snapshot` to the restore form and returns the page it redirects
+/// to, which shows the result.
+async fn restore_by_post(client: &Client, snapshot: &str, mode: &str) -> String {
+    let res = post_file(client, "/backup", snapshot, &[("mode", mode)]).await;
+    assert_eq!(res.status(), Status::SeeOther);
+    let location = res.headers().get_one("Location").unwrap().to_string();
+    client
+        .get(location)
+        .dispatch()
+        .await
+        .into_string()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn backup_restores_into_empty_household_with_new_ids() {
+    run_test!(|client, conn| {
+        // --- Arrange: A labeled task done twice, and a backup of it ---
+        insert_label_by_post(&client, "kitchen", "#eeeeee").await;
+        let household_id = household_id(&conn).await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+        let task_id = Task::all(household_id, &conn).await[0].id.unwrap();
+        update_task_by_post(
+            &client,
+            task_id,
+            "sink",
+            "scrub it",
+            "2000-01-01",
+            &[label_id],
+        )
+        .await;
+        assert!(
+            Task::update_to_today(
+                task_id,
+                household_id,
+                Some("shiny".to_string()),
+                None,
+                &conn
+            )
+            .await
+        );
+        let res = client.get("/backup.json").dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.content_type(), Some(ContentType::JSON));
+        let snapshot = res.into_string().await.unwrap();
+        let before = Task::all_by_due(household_id, &conn).await.remove(0);
+        let history_before = Completion::for_task(task_id, &conn).await;
+
+        // --- Act: Delete everything and restore the backup ---
+        Task::delete_all(&conn).await;
+        Label::delete_all(&conn).await;
+        let msg = restore_by_post(&client, &snapshot, "empty").await;
+
+        // --- Assert: Same task, history and label under new ids ---
+        assert!(
+            msg.contains("Restored 1 task(s) and 1 new label(s)."),
+            "{msg}"
+        );
+        let labels = Label::all(household_id, &conn).await;
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "kitchen");
+        let tasks = Task::all_by_due(household_id, &conn).await;
+        assert_eq!(tasks.len(), 1);
+        let after = &tasks[0];
+        assert_ne!(after.task.id, Some(task_id));
+        assert_eq!(after.task.name, before.task.name);
+        assert_eq!(after.task.description, "scrub it");
+        assert_eq!(after.task.updated_at, before.task.updated_at);
+        assert_eq!(after.label_ids, vec![labels[0].id.unwrap()]);
+        let history = Completion::for_task(after.task.id.unwrap(), &conn).await;
+        assert_eq!(history.len(), history_before.len());
+        assert_eq!(history[0].note.as_deref(), Some("shiny"));
+        assert_eq!(history[0].completed_at, history_before[0].completed_at);
+    })
+}
+
+#[test]
+fn backup_merges_only_when_asked() {
+    run_test!(|client, conn| {
+        // --- Arrange: A backup of one labeled task ---
+        insert_label_by_post(&client, "kitchen", "#eeeeee").await;
+        let household_id = household_id(&conn).await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+        let task_id = Task::all(household_id, &conn).await[0].id.unwrap();
+        update_task_by_post(&client, task_id, "sink", "", "", &[label_id]).await;
+        let snapshot = client
+            .get("/backup.json")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+
+        // --- Act & Assert: Not restored into a household with tasks ---
+        let msg = restore_by_post(&client, &snapshot, "empty").await;
+        assert!(msg.contains("already has tasks"), "{msg}");
+        assert_eq!(Task::all(household_id, &conn).await.len(), 1);
+
+        // --- Act & Assert: Merged, sharing the label of the same name ---
+        let msg = restore_by_post(&client, &snapshot, "merge").await;
+        assert!(
+            msg.contains("Restored 1 task(s) and 0 new label(s)."),
+            "{msg}"
+        );
+        assert_eq!(Label::all(household_id, &conn).await.len(), 1);
+        let tasks = Task::all_by_due(household_id, &conn).await;
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|t| t.label_ids == vec![label_id]));
+    })
+}
+
+#[test]
+fn invalid_backup_restores_nothing() {
+    run_test!(|client, conn| {
+        let household_id = household_id(&conn).await;
+        // A good task and label, then a task with an unknown label
+        let snapshot = r##"{
+            "version": 1,
+            "labels": [{"id": 1, "name": "kitchen", "color_hex": "#eeeeee"}],
+            "tasks": [
+                {"id": 1, "name": "sink", "updated_at": "2000-01-01T00:00:00", "label_ids": [1]},
+                {"id": 2, "name": "fan", "updated_at": "2000-01-01T00:00:00", "label_ids": [9]}
+            ]
+        }"##;
+
+        let msg = restore_by_post(&client, snapshot, "empty").await;
+        assert!(msg.contains("Task 2: No label with id 9."), "{msg}");
+        assert!(Task::all(household_id, &conn).await.is_empty());
+        assert!(Label::all(household_id, &conn).await.is_empty());
+
+        // An unknown version isn't read at all
+        let msg = restore_by_post(&client, r#"{"version": 99}"#, "empty").await;
+        assert!(msg.contains("Unsupported version 99."), "{msg}");
+
+        let msg = restore_by_post(&client, "not json", "empty").await;
+        assert!(msg.contains("Invalid backup"), "{msg}");
+    })
+}
+
+/// A request a webhook stand-in got: its headers, lowercased, and body.
+type HookRequest = (HashMap<String, String>, String);
+
+/// Starts a local HTTP server standing in for a webhook. It answers with
+/// `statuses` in turn and then with 200, and sends the requests it gets to
+/// the returned channel.
+async fn webhook_stand_in(statuses: Vec<u16>) -> (String, UnboundedReceiver<HookRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (sender, receiver) = unbounded_channel();
+    rocket::tokio::spawn(async move {
+        let mut statuses = statuses.into_iter();
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut headers = HashMap::new();
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            loop {
+                line.clear();
+                stream.read_line(&mut line).await.unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) => {
+                        headers.insert(name.to_lowercase(), value.to_string());
+                    }
+                    None => break,
+                }
+            }
+            let length = headers["content-length"].parse().unwrap();
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).await.unwrap();
+            let status = statuses.next().unwrap_or(200);
+            let response = format!(
+                "HTTP/1.1 {status} Stand-in\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            sender
+                .send((headers, String::from_utf8(body).unwrap()))
+                .unwrap();
+        }
+    });
+    (url, receiver)
+}
+
+/// Waits for deliveries to be logged, as they are after webhooks answer.
+async fn wait_for_deliveries(
+    count: usize,
+    household_id: i32,
+    conn: &super::DbConn,
+) -> Vec<Delivery> {
+    for _ in 0..100 {
+        let deliveries = Delivery::recent(household_id, conn).await;
+        if deliveries.len() >= count {
+            return deliveries;
+        }
+        rocket::tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("deliveries weren't logged");
+}
+
+#[test]
+fn webhook_gets_signed_event_and_retried_delivery() {
+    run_test!(|client, conn| {
+        // --- Arrange: A webhook failing once, and a task ---
+        let (url, mut requests) = webhook_stand_in(vec![500]).await;
+        let res = client
+            .post("/webhooks")
+            .header(ContentType::Form)
+            .body(format!(
+                "url={url}&secret=s3cret&events=task.completed&events=task.deleted"
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+        let household_id = household_id(&conn).await;
+        assert_eq!(Webhook::all(household_id, &conn).await.len(), 1);
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+        let id = Task::all(household_id, &conn).await[0].id.unwrap();
+
+        // --- Act: "I did it today!" ---
+        client.post(format!("/{id}/date")).dispatch().await;
+
+        // --- Assert: The event is sent again after the failure ---
+        for _ in 0..2 {
+            let (headers, body) =
+                rocket::tokio::time::timeout(std::time::Duration::from_secs(5), requests.recv())
+                    .await
+                    .expect("webhook request")
+                    .unwrap();
+            assert_eq!(headers["x-last-date-event"], "task.completed");
+            assert_eq!(
+                headers["x-last-date-signature"],
+                super::webhook::sign("s3cret", &body)
+            );
+            let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(payload["event"], "task.completed");
+            assert_eq!(payload["household_id"], household_id);
+            assert_eq!(payload["data"]["id"], id);
+            assert_eq!(payload["data"]["name"], "sink");
+        }
+        let deliveries = wait_for_deliveries(2, household_id, &conn).await;
+        assert_eq!(deliveries[0].attempt, 2);
+        assert_eq!(deliveries[0].status_code, Some(200));
+        assert_eq!(deliveries[1].attempt, 1);
+        assert_eq!(deliveries[1].status_code, Some(500));
+        // Creating the task isn't subscribed to
+        assert!(requests.try_recv().is_err());
+
+        // --- Act & Assert: The log page shows the attempts ---
+        let body = client
+            .get("/webhooks")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert_eq!(body.matches("<td>task.completed</td>").count(), 2);
+    })
+}
+
+#[test]
+fn webhook_delivery_gives_up_after_max_attempts() {
+    run_test!(|client, conn| {
+        // --- Arrange: A webhook nobody listens to ---
+        let port = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        client
+            .post("/webhooks")
+            .header(ContentType::Form)
+            .body(format!(
+                "url=http://127.0.0.1:{port}/hook&secret=s3cret&events=label.created"
+            ))
+            .dispatch()
+            .await;
+
+        // --- Act: Add a label ---
+        insert_label_by_post(&client, "kitchen", "#eeeeee").await;
+
+        // --- Assert: Each attempt is logged with its error ---
+        let household_id = household_id(&conn).await;
+        let deliveries = wait_for_deliveries(5, household_id, &conn).await;
+        rocket::tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let deliveries_later = Delivery::recent(household_id, &conn).await;
+        assert_eq!(deliveries_later.len(), 5);
+        assert_eq!(deliveries[0].attempt, 5);
+        assert!(deliveries.iter().all(|d| d.event == "label.created"));
+        assert!(
+            deliveries
+                .iter()
+                .all(|d| d.status_code.is_none() && d.error.is_some())
+        );
+    })
+}
+
+#[test]
+fn webhook_gets_changes_from_command_line() {
+    run_test!(|client, conn| {
+        // --- Arrange: A webhook and a task ---
+        let (url, mut requests) = webhook_stand_in(vec![]).await;
+        client
+            .post("/webhooks")
+            .header(ContentType::Form)
+            .body(format!("url={url}&secret=s3cret&events=task.completed"))
+            .dispatch()
+            .await;
+        insert_task_by_post(&client, "sink", "", "", &[]).await;
+
+        let config = super::webhook::Config {
+            poll_interval_ms: 0,
+            ..Default::default()
+        };
+        assert!(!config.poll_interval().is_zero());
+
+        // --- Act: Done from the command line ---
+        run_cli(&conn, &["done", "sink"]).await.unwrap();
+
+        // --- Assert: The event is sent and logged ---
+        let (headers, body) =
+            rocket::tokio::time::timeout(std::time::Duration::from_secs(5), requests.recv())
+                .await
+                .expect("webhook request")
+                .unwrap();
+        assert_eq!(headers["x-last-date-event"], "task.completed");
+        let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(payload["data"]["name"], "sink");
+        let household_id = household_id(&conn).await;
+        let deliveries = wait_for_deliveries(1, household_id, &conn).await;
+        assert_eq!(deliveries[0].status_code, Some(200));
+    })
+}
+
+/// Starts a local SMTP server which accepts every message, and sends their
+/// contents to the returned channel.
+async fn smtp_sink() -> (u16, UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = unbounded_channel();
+    rocket::tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            stream.write_all(b"220 sink\r\n").await.unwrap();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let command = line.to_uppercase();
+                if command.starts_with("DATA") {
+                    stream.write_all(b"354 go on\r\n").await.unwrap();
+                    let mut data = String::new();
+                    loop {
+                        line.clear();
+                        stream.read_line(&mut line).await.unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&line);
+                    }
+                    sender.send(data).unwrap();
+                    stream.write_all(b"250 queued\r\n").await.unwrap();
+                } else if command.starts_with("QUIT") {
+                    stream.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    stream.write_all(b"250 ok\r\n").await.unwrap();
+                }
+            }
+        }
+    });
+    (port, receiver)
+}
+
+#[test]
+fn reminders_are_sent_once_per_last_date() {
+    run_test!(|client, conn| {
+        // --- Arrange: An email address and tasks to do, or not ---
+        let res = client
+            .post("/user/email")
+            .header(ContentType::Form)
+            .body("email=tester@example.com")
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+        let household_id = household_id(&conn).await;
+        assert!(Task::insert_with_old_date("fan", household_id, &conn).await);
+        assert!(Task::insert_with_old_date("sink", household_id, &conn).await);
+        insert_task_by_post(&client, "shopping", "", "", &[]).await;
+        let tasks = Task::all(household_id, &conn).await;
+        let fan_id = tasks.iter().find(|t| t.name == "fan").unwrap().id.unwrap();
+        let sink_id = tasks.iter().find(|t| t.name == "sink").unwrap().id.unwrap();
+        client
+            .post(format!("/{sink_id}"))
+            .header(ContentType::Form)
+            .body("name=sink&description=&updated_at=&interval_count=1&interval_unit=weeks")
+            .dispatch()
+            .await;
+        let (port, mut messages) = smtp_sink().await;
+        let config = super::reminder::Config {
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: Some(port),
+            tls: super::reminder::Encryption::None,
+            from: "last-date <noreply@example.com>".to_string(),
+            ..Default::default()
+        };
+        let pool = super::DbConn::pool(client.rocket()).unwrap();
+        let zero = super::reminder::Config {
+            check_interval_secs: 0,
+            ..config.clone()
+        };
+        assert!(!zero.check_interval().is_zero());
+
+        // --- Act: Send reminders ---
+        let sent = super::reminder::send_reminders(pool, &config).await;
+
+        // --- Assert: A digest of the stale and overdue tasks ---
+        assert_eq!(sent, Ok(1));
+        let message = messages.try_recv().unwrap();
+        assert!(message.contains("To: tester@example.com"), "{message}");
+        assert!(
+            message.contains("- fan (last done 2000-01-01)"),
+            "{message}"
+        );
+        assert!(
+            message.contains("- sink (last done 2000-01-01, due 2000-01-08)"),
+            "{message}"
+        );
+        assert!(!message.contains("shopping"), "{message}");
+
+        // --- Act & Assert: Nothing new, nothing sent ---
+        let sent = super::reminder::send_reminders(pool, &config).await;
+        assert_eq!(sent, Ok(0));
+        assert!(messages.try_recv().is_err());
+
+        // --- Act & Assert: A task done again long ago is reminded of again ---
+        update_task_by_post(&client, fan_id, "fan", "", "2000-02-01", &[]).await;
+        let sent = super::reminder::send_reminders(pool, &config).await;
+        assert_eq!(sent, Ok(1));
+        let message = messages.try_recv().unwrap();
+        assert!(
+            message.contains("- fan (last done 2000-02-01)"),
+            "{message}"
+        );
+        assert!(!message.contains("sink"), "{message}");
+    })
+}
+
+#[test]
+fn invalid_email_address_is_rejected() {
+    run_test!(|client, conn| {
+        client
+            .post("/user/email")
+            .header(ContentType::Form)
+            .body("email=not an address")
+            .dispatch()
+            .await;
+        let user = User::authenticate(
+            Credentials {
+                name: TEST_USER.to_string(),
+                password: TEST_PASSWORD.to_string(),
+            },
+            &conn,
+        )
+        .await
+        .unwrap();
+        assert_eq!(user.email, None);
+    })
+}
+
+/// Returns names of the tasks found by `/search?q=`, in order, with matches
+/// wrapped in `[]`.
+async fn search_by_get(client: &Client, q: &str) -> Vec<String> {
+    let res = client.get(format!("/search?q={q}")).dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+    let document = Html::parse_document(&res.into_string().await.unwrap());
+    let selector = Selector::parse("tbody tr td:nth-child(2) a").unwrap();
+    document
+        .select(&selector)
+        .map(|a| {
+            a.inner_html()
+                .replace("<mark>", "[")
+                .replace("</mark>", "]")
+        })
+        .collect()
+}
+
+#[test]
+fn search_ranks_and_highlights_matching_tasks() {
+    run_test!(|client, conn| {
+        // --- Arrange: Tasks, one of them in another household ---
+        let household_id = household_id(&conn).await;
+        for name in ["Water plants", "Clean bathroom", "Buy plant food"] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+        }
+        let tasks = Task::all(household_id, &conn).await;
+        let id_of = |name: &str| tasks.iter().find(|t| t.name == name).unwrap().id.unwrap();
+        insert_label_by_post(&client, "garden", "#00ff00").await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        update_task_by_post(
+            &client,
+            id_of("Water plants"),
+            "Water plants",
+            "Plants on the balcony, and the plants in the kitchen.",
+            "",
+            &[label_id],
+        )
+        .await;
+        let user_id = conn
+            .run(|c| User::id_by_name(TEST_USER, c))
+            .await
+            .unwrap()
+            .unwrap();
+        let form = HouseholdForm {
+            name: "neighbors".to_string(),
+        };
+        let other = Household::insert(form, user_id, &conn).await.unwrap();
+        assert!(Task::insert_with_old_date("Plant trees", other, &conn).await);
+
+        // --- Act & Assert: The task mentioning plants most comes first ---
+        assert_eq!(
+            search_by_get(&client, "plant").await,
+            vec!["Water [plants]", "Buy [plant] food"]
+        );
+        let res = client.get("/search?q=plant").dispatch().await;
+        let body = res.into_string().await.unwrap();
+        assert!(
+            body.contains("<mark>Plants</mark> on the balcony"),
+            "{body}"
+        );
+        assert!(body.contains("garden"));
+
+        // --- Act & Assert: Every word must match ---
+        assert_eq!(
+            search_by_get(&client, "plant+food").await,
+            vec!["Buy [plant] [food]"]
+        );
+        assert!(search_by_get(&client, "%22").await.is_empty());
+
+        // --- Act & Assert: Updates and deletions are searched ---
+        let id = id_of("Clean bathroom");
+        update_task_by_post(&client, id, "Clean greenhouse", "", "", &[]).await;
+        assert_eq!(
+            search_by_get(&client, "bathroom").await,
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            search_by_get(&client, "green").await,
+            vec!["Clean [greenhouse]"]
+        );
+        assert!(Task::delete_with_id(id, household_id, None, &conn).await);
+        assert!(search_by_get(&client, "green").await.is_empty());
+    })
+}
+
+/// Returns names of the tasks on the index page with the query string, in
+/// order.
+async fn index_task_names(client: &Client, query: &str) -> Vec<String> {
+    let res = client.get(format!("/?{query}")).dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+    let document = Html::parse_document(&res.into_string().await.unwrap());
+    let selector = Selector::parse("tbody tr td:nth-child(2) a").unwrap();
+    document.select(&selector).map(|a| a.inner_html()).collect()
+}
+
+#[test]
+fn index_sorts_and_filters_by_query_parameters() {
+    run_test!(|client, conn| {
+        // --- Arrange: Tasks done on different days with different labels ---
+        let household_id = household_id(&conn).await;
+        insert_label_by_post(&client, "kitchen", "#ff0000").await;
+        insert_label_by_post(&client, "bath", "#0000ff").await;
+        let labels = Label::all(household_id, &conn).await;
+        let label_id = |name: &str| labels.iter().find(|l| l.name == name).unwrap().id.unwrap();
+        let (kitchen, bath) = (label_id("kitchen"), label_id("bath"));
+        let today = Local::now().date_naive();
+        let days_ago = |n| (today - Duration::days(n)).format("%Y-%m-%d").to_string();
+        for (name, done, label_ids) in [
+            ("oven", days_ago(60), vec![kitchen]),
+            ("bathtub", days_ago(10), vec![bath, kitchen]),
+            ("curtains", days_ago(100), vec![]),
+            ("sink", days_ago(1), vec![kitchen]),
+        ] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+            let id = Task::all(household_id, &conn)
+                .await
+                .into_iter()
+                .find(|t| t.name == name)
+                .unwrap()
+                .id
+                .unwrap();
+            update_task_by_post(&client, id, name, "", &done, &label_ids).await;
+        }
+
+        // --- Act & Assert ---
+        assert_eq!(
+            index_task_names(&client, "").await,
+            vec!["curtains", "oven", "bathtub", "sink"]
+        );
+        assert_eq!(
+            index_task_names(&client, "sort=name").await,
+            vec!["bathtub", "curtains", "oven", "sink"]
+        );
+        assert_eq!(
+            index_task_names(&client, "sort=date&order=desc").await,
+            vec!["sink", "bathtub", "oven", "curtains"]
+        );
+        // By the first label, "bath" before "kitchen", and unlabeled last.
+        assert_eq!(
+            index_task_names(&client, "sort=label").await,
+            vec!["bathtub", "oven", "sink", "curtains"]
+        );
+        assert_eq!(
+            index_task_names(&client, &format!("sort=name&label={kitchen}")).await,
+            vec!["bathtub", "oven", "sink"]
+        );
+        assert_eq!(
+            index_task_names(&client, "unlabeled=true").await,
+            vec!["curtains"]
+        );
+        assert_eq!(
+            index_task_names(&client, "older_than=30d&sort=name").await,
+            vec!["curtains", "oven"]
+        );
+        assert_eq!(
+            index_task_names(&client, &format!("older_than=30d&label={kitchen}")).await,
+            vec!["oven"]
+        );
+        // Empty and invalid values are ignored, like the filter bar sends.
+        assert_eq!(
+            index_task_names(&client, "sort=bogus&label=&older_than=").await,
+            vec!["curtains", "oven", "bathtub", "sink"]
+        );
+        // Also those ending in multibyte characters, like `3é` and `€`.
+        assert_eq!(
+            index_task_names(&client, "older_than=3%C3%A9&due_within=%E2%82%AC").await,
+            vec!["curtains", "oven", "bathtub", "sink"]
+        );
+    })
+}
+
+#[test]
+fn deleted_task_goes_to_trash_until_restored_or_purged() {
+    run_test!(|client, conn| {
+        // --- Arrange: Two tasks done before ---
+        let household_id = household_id(&conn).await;
+        for name in ["fan", "sink"] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+        }
+        let tasks = Task::all(household_id, &conn).await;
+        let id_of = |name: &str| tasks.iter().find(|t| t.name == name).unwrap().id.unwrap();
+        let (fan, sink) = (id_of("fan"), id_of("sink"));
+
+        // --- Act: Delete both ---
+        for id in [fan, sink] {
+            let res = client.delete(format!("/{id}")).dispatch().await;
+            assert_eq!(res.status(), Status::SeeOther);
+        }
+
+        // --- Assert: They are only in the trash ---
+        assert!(index_task_names(&client, "").await.is_empty());
+        assert_eq!(
+            client.get(format!("/{fan}")).dispatch().await.status(),
+            Status::NotFound
+        );
+        let res = client.get("/trash").dispatch().await;
+        let body = res.into_string().await.unwrap();
+        assert!(body.contains("fan") && body.contains("sink"), "{body}");
+
+        // --- Act & Assert: A restored task comes back with its history ---
+        let res = client
+            .post(format!("/trash/{fan}/restore"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::SeeOther);
+        assert_eq!(index_task_names(&client, "").await, vec!["fan"]);
+        assert_eq!(Completion::for_task(fan, &conn).await.len(), 1);
+
+        // --- Act & Assert: A purged task is gone for good ---
+        let res = client.delete(format!("/trash/{sink}")).dispatch().await;
+        assert_eq!(res.status(), Status::SeeOther);
+        let body = client
+            .get("/trash")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(!body.contains("sink"), "{body}");
+        assert_eq!(Task::all_by_id(&conn).await.len(), 1);
+        assert!(Completion::for_task(sink, &conn).await.is_empty());
+
+        // --- Act & Assert: Tasks not in the trash can't be purged ---
+        client.delete(format!("/trash/{fan}")).dispatch().await;
+        assert_eq!(index_task_names(&client, "").await, vec!["fan"]);
+    })
+}
+
+#[test]
+fn trash_is_purged_after_configured_days() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task in the trash ---
+        insert_task_by_post(&client, "fan", "", "", &[]).await;
+        let id = Task::all_by_id(&conn).await[0].id.unwrap();
+        assert!(Task::delete_with_id(id, household_id(&conn).await, None, &conn).await);
+        let pool = super::DbConn::pool(client.rocket()).unwrap();
+        let now = Local::now().naive_local();
+
+        // --- Act & Assert: Not purged before its time ---
+        let purged = super::trash::purge(pool, now - Duration::days(30)).await;
+        assert_eq!(purged, Ok(0));
+        assert_eq!(Task::all_by_id(&conn).await.len(), 1);
+
+        // --- Act & Assert: Purged after it ---
+        let purged = super::trash::purge(pool, now + Duration::seconds(1)).await;
+        assert_eq!(purged, Ok(1));
+        assert!(Task::all_by_id(&conn).await.is_empty());
+
+        // --- Act & Assert: A zero interval doesn't stop the purges ---
+        let config = super::trash::Config {
+            check_interval_secs: 0,
+            ..Default::default()
+        };
+        assert!(!config.check_interval().is_zero());
+    })
+}
+
+#[test]
+fn archived_task_is_hidden_from_index_only() {
+    run_test!(|client, conn| {
+        // --- Arrange: Two tasks ---
+        for name in ["heater", "fan"] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+        }
+        let tasks = Task::all_by_id(&conn).await;
+        let heater = tasks
+            .iter()
+            .find(|t| t.name == "heater")
+            .unwrap()
+            .id
+            .unwrap();
+
+        // --- Act: Archive one of them ---
+        let res = client.post(format!("/{heater}/archive")).dispatch().await;
+        assert_eq!(res.status(), Status::SeeOther);
+
+        // --- Assert: It's listed as archived, and can still be edited ---
+        assert_eq!(index_task_names(&client, "").await, vec!["fan"]);
+        assert_eq!(
+            index_task_names(&client, "archived=true").await,
+            vec!["heater"]
+        );
+        let res = client.get(format!("/{heater}")).dispatch().await;
+        let body = res.into_string().await.unwrap();
+        assert!(body.contains("Unarchive this task"), "{body}");
+
+        // --- Act & Assert: Unarchived, it's back on the index ---
+        client.post(format!("/{heater}/unarchive")).dispatch().await;
+        assert_eq!(
+            index_task_names(&client, "sort=name").await,
+            vec!["fan", "heater"]
+        );
+        assert!(index_task_names(&client, "archived=true").await.is_empty());
+    })
+}
+
+/// Returns the path of the undo button on the page, if any.
+async fn undo_path(client: &Client, uri: &str) -> Option<String> {
+    let res = client.get(uri).dispatch().await;
+    let document = Html::parse_document(&res.into_string().await.unwrap());
+    let selector = Selector::parse("form[action^='/undo/']").unwrap();
+    document
+        .select(&selector)
+        .next()
+        .and_then(|f| f.value().attr("action"))
+        .map(str::to_string)
+}
+
+#[test]
+fn did_it_today_can_be_undone_once() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task done long ago ---
+        let household_id = household_id(&conn).await;
+        assert!(Task::insert_with_old_date("fan", household_id, &conn).await);
+        let task = Task::all_by_id(&conn).await.remove(0);
+        let id = task.id.unwrap();
+        client.post(format!("/{id}/date")).dispatch().await;
+        let done = Task::task_by_id(id, household_id, &conn).await.unwrap();
+        assert_ne!(done.updated_at, task.updated_at);
+
+        // --- Act: Undo from the flash message ---
+        let path = undo_path(&client, "/").await.expect("undo button");
+        let res = client.post(&path).dispatch().await;
+
+        // --- Assert: The previous date is back ---
+        assert_eq!(res.status(), Status::SeeOther);
+        let undone = Task::task_by_id(id, household_id, &conn).await.unwrap();
+        assert_eq!(undone.updated_at, task.updated_at);
+        assert!(Completion::for_task(id, &conn).await.is_empty());
+        // The button is shown only once, and works only once.
+        assert_eq!(undo_path(&client, "/").await, None);
+        client.post(&path).dispatch().await;
+        let res = client.get("/").dispatch().await;
+        assert!(
+            res.into_string()
+                .await
+                .unwrap()
+                .contains("too late to undo")
+        );
+    })
+}
+
+#[test]
+fn deletions_can_be_undone_within_window() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task with a label ---
+        let household_id = household_id(&conn).await;
+        insert_task_by_post(&client, "fan", "", "", &[]).await;
+        insert_label_by_post(&client, "summer", "#ffff00").await;
+        let id = Task::all_by_id(&conn).await[0].id.unwrap();
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        update_task_by_post(&client, id, "fan", "", "", &[label_id]).await;
+
+        // --- Act & Assert: The deleted label comes back on the task ---
+        client.delete(format!("/label/{label_id}")).dispatch().await;
+        let path = undo_path(&client, "/label").await.expect("undo button");
+        let res = client.post(path).dispatch().await;
+        assert_eq!(res.headers().get_one("Location"), Some("/label"));
+        let task = Task::summary_by_id(id, household_id, &conn).await.unwrap();
+        assert_eq!(task.label_ids, vec![label_id]);
+
+        // --- Act & Assert: The deleted task comes back from the trash ---
+        client.delete(format!("/{id}")).dispatch().await;
+        let path = undo_path(&client, "/").await.expect("undo button");
+        client.post(path).dispatch().await;
+        assert_eq!(index_task_names(&client, "").await, vec!["fan"]);
+
+        // --- Act & Assert: Too late to undo ---
+        client.delete(format!("/{id}")).dispatch().await;
+        let path = undo_path(&client, "/").await.expect("undo button");
+        assert!(Undo::expire_all(&conn).await);
+        client.post(path).dispatch().await;
+        assert!(index_task_names(&client, "").await.is_empty());
+    })
+}
+
+#[test]
+fn stats_show_intervals_and_most_neglected_label() {
+    run_test!(|client, conn| {
+        // --- Arrange: Tasks done on several days with labels ---
+        let household_id = household_id(&conn).await;
+        insert_label_by_post(&client, "bath", "#0000ff").await;
+        insert_label_by_post(&client, "kitchen", "#ff0000").await;
+        let labels = Label::all(household_id, &conn).await;
+        let label_id = |name: &str| labels.iter().find(|l| l.name == name).unwrap().id.unwrap();
+        let today = Local::now().date_naive();
+        let days_ago = |n| (today - Duration::days(n)).format("%Y-%m-%d").to_string();
+        for (name, days, label) in [
+            ("bathtub", [100, 60, 20].as_slice(), "bath"),
+            ("oven", [200, 190].as_slice(), "kitchen"),
+        ] {
+            insert_task_by_post(&client, name, "", "", &[]).await;
+            let id = Task::all(household_id, &conn)
+                .await
+                .into_iter()
+                .find(|t| t.name == name)
+                .unwrap()
+                .id
+                .unwrap();
+            let (&first, rest) = days.split_first().unwrap();
+            update_task_by_post(&client, id, name, "", &days_ago(first), &[label_id(label)]).await;
+            for &n in rest {
+                complete_task_by_post(&client, id, name, &days_ago(n), &[label_id(label)]).await;
+            }
+        }
+
+        // --- Act ---
+        let stats = Stats::of(household_id, &conn).await;
+
+        // --- Assert: The task longest overdue compared with usual first ---
+        let names: Vec<&str> = stats.tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["oven", "bathtub"]);
+        let bathtub = &stats.tasks[1];
+        assert_eq!(bathtub.completions, 3);
+        assert_eq!(bathtub.mean_interval, Some(40.0));
+        assert_eq!(bathtub.median_interval, Some(40.0));
+        assert_eq!(bathtub.max_interval, Some(40));
+        assert_eq!(bathtub.current_gap, 20);
+        assert_eq!(bathtub.gap_ratio, Some(0.5));
+        assert_eq!(stats.tasks[0].gap_ratio, Some(19.0));
+        assert_eq!(stats.most_neglected, Some(label_id("kitchen")));
+        assert_eq!(stats.months.len(), 12);
+        assert_eq!(stats.months[11], today.format("%Y-%m").to_string());
+        let bath = stats
+            .labels
+            .iter()
+            .find(|l| l.label.name == "bath")
+            .unwrap();
+        assert_eq!(bath.monthly.iter().sum::<usize>(), 3);
+
+        let res = client.get("/stats").dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+        let body = res.into_string().await.unwrap();
+        assert!(
+            body.contains("19.0x usual") || body.contains("19x usual"),
+            "{body}"
+        );
+        assert!(body.contains("most neglected"));
+    })
+}
+
+#[test]
+fn task_and_label_pages_chart_completions() {
+    run_test!(|client, conn| {
+        // --- Arrange: A labeled task done 30 and 10 days ago, and today ---
+        let household_id = household_id(&conn).await;
+        insert_label_by_post(&client, "bath", "#0000ff").await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        insert_task_by_post(&client, "bathtub", "", "", &[]).await;
+        let id = Task::all_by_id(&conn).await[0].id.unwrap();
+        let today = Local::now().date_naive();
+        let days_ago = |n| (today - Duration::days(n)).format("%Y-%m-%d").to_string();
+        update_task_by_post(&client, id, "bathtub", "", &days_ago(30), &[label_id]).await;
+        complete_task_by_post(&client, id, "bathtub", &days_ago(10), &[label_id]).await;
+        client.post(format!("/{id}/date")).dispatch().await;
+
+        for uri in [format!("/{id}"), format!("/label/{label_id}")] {
+            // --- Act ---
+            let res = client.get(&uri).dispatch().await;
+
+            // --- Assert: A day a cell, and a bar an interval ---
+            let document = Html::parse_document(&res.into_string().await.unwrap());
+            let cells = Selector::parse("svg.heatmap rect").unwrap();
+            let titles: Vec<String> = document
+                .select(&cells)
+                .map(|r| r.text().collect())
+                .collect();
+            let weekday = i64::from(today.weekday().num_days_from_sunday());
+            assert_eq!(titles.len() as i64, 52 * 7 + weekday + 1, "{uri}");
+            assert!(titles.contains(&format!("{}: 1 done", days_ago(30))));
+            assert!(titles.contains(&format!("{}: 0 done", days_ago(20))));
+            let bars = Selector::parse("svg.intervals rect").unwrap();
+            let bars: Vec<String> = document.select(&bars).map(|r| r.text().collect()).collect();
+            assert_eq!(
+                bars,
+                vec![
+                    format!("{}: 20 day(s)", days_ago(10)),
+                    format!("{}: 10 day(s)", days_ago(0)),
+                ],
+                "{uri}"
+            );
+        }
+    })
+}
+
+/// Runs `last-date-cli` with the arguments in the household of the test user.
+async fn run_cli(conn: &super::DbConn, args: &[&str]) -> Result<String, String> {
+    let household = household_id(conn).await.to_string();
+    let head = ["last-date-cli", "--household", &household];
+    let args = head.iter().chain(args).copied();
+    cli::run(Args::parse_from(args), conn).await
+}
+
+#[test]
+fn cli_manages_tasks_and_labels() {
+    run_test!(|_client, conn| {
+        let household_id = household_id(&conn).await;
+
+        // --- Act & Assert: Labels and tasks are added ---
+        let out = run_cli(&conn, &["label", "add", "kitchen"]).await.unwrap();
+        assert!(
+            out.contains("kitchen") && out.contains(DEFAULT_COLOR),
+            "{out}"
+        );
+        assert!(
+            run_cli(&conn, &["label", "add", "bad", "--color", "red"])
+                .await
+                .is_err()
+        );
+        run_cli(&conn, &["add", "Vacuum the bedroom"])
+            .await
+            .unwrap();
+        run_cli(&conn, &["add", "Vacuum the kitchen", "-l", "kitchen"])
+            .await
+            .unwrap();
+        assert!(run_cli(&conn, &["add", "x", "-l", "nope"]).await.is_err());
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        let tasks = Task::tasks_by_label(label_id, household_id, &conn)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].task.name, "Vacuum the kitchen");
+
+        // --- Act & Assert: Tasks are done by fuzzy names ---
+        let err = run_cli(&conn, &["done", "vacuum"]).await.unwrap_err();
+        assert!(err.contains("2 tasks match"), "{err}");
+        assert!(run_cli(&conn, &["done", "dishes"]).await.is_err());
+        let out = run_cli(&conn, &["done", "VACBED", "--note", "quick"])
+            .await
+            .unwrap();
+        assert!(out.contains("Vacuum the bedroom"), "{out}");
+        let id = Task::all(household_id, &conn)
+            .await
+            .into_iter()
+            .find(|t| t.name == "Vacuum the bedroom")
+            .unwrap()
+            .id
+            .unwrap();
+        assert_eq!(Completion::for_task(id, &conn).await.len(), 2);
+
+        // --- Act & Assert: Lists are tables or JSON ---
+        let out = run_cli(&conn, &["list"]).await.unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert!(lines[0].starts_with("ID"), "{out}");
+        assert_eq!(lines[0].find("LAST DONE"), lines[1].find(&today), "{out}");
+        assert!(lines[1].contains("Vacuum the kitchen  "), "{out}");
+        assert!(lines[1].ends_with("kitchen"), "{out}");
+        let out = run_cli(&conn, &["list", "--json"]).await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json[1]["name"], "Vacuum the bedroom");
+
+        // --- Act & Assert: Archived tasks are listed apart, like on the web ---
+        assert!(
+            Task::set_archived(id, household_id, true, None, &conn)
+                .await
+                .is_ok()
+        );
+        let out = run_cli(&conn, &["list"]).await.unwrap();
+        assert!(!out.contains("Vacuum the bedroom"), "{out}");
+        let out = run_cli(&conn, &["list", "--archived"]).await.unwrap();
+        assert!(out.contains("Vacuum the bedroom"), "{out}");
+        assert!(!out.contains("Vacuum the kitchen"), "{out}");
+        let out = run_cli(&conn, &["done", "vacuum"]).await.unwrap();
+        assert!(out.contains("Vacuum the kitchen"), "{out}");
+
+        // --- Act & Assert: Labels are renamed and deleted ---
+        run_cli(&conn, &["label", "edit", "kitchen", "--name", "cooking"])
+            .await
+            .unwrap();
+        assert_eq!(Label::all(household_id, &conn).await[0].name, "cooking");
+        run_cli(&conn, &["label", "delete", &label_id.to_string()])
+            .await
+            .unwrap();
+        assert!(Label::all(household_id, &conn).await.is_empty());
+        assert_eq!(Task::all(household_id, &conn).await.len(), 2);
+    })
+}
+
+/// Returns the text of the terminal UI drawn on a 100x10 screen, a line per
+/// row.
+fn draw_tui(app: &App) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..10)
+        .map(|y| (0..100).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect()
+}
+
+async fn press(app: &mut App, keys: &str, conn: &super::DbConn) {
+    for c in keys.chars() {
+        let code = match c {
+            '\n' => KeyCode::Enter,
+            c => KeyCode::Char(c),
+        };
+        app.handle_key(KeyEvent::from(code), conn).await;
+    }
+}
+
+#[test]
+fn tui_shows_tasks_and_handles_keys() {
+    run_test!(|client, conn| {
+        // --- Arrange: A labeled task and an older unlabeled one ---
+        let household_id = household_id(&conn).await;
+        insert_label_by_post(&client, "kitchen", "#ff0000").await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        assert!(Task::insert_with_old_date("Laundry", household_id, &conn).await);
+        insert_task_by_post(&client, "Dishes", "", "", &[]).await;
+        let dishes = Task::all(household_id, &conn).await[1].id.unwrap();
+        update_task_by_post(&client, dishes, "Dishes", "", "", &[label_id]).await;
+        let household = Household::all(&conn)
+            .await
+            .into_iter()
+            .find(|h| h.id == household_id)
+            .unwrap();
+        let mut app = App::new(household, &conn).await;
+
+        // --- Assert: The oldest task first, labels in their colors ---
+        let screen = draw_tui(&app);
+        assert!(screen[1].contains("Last updated"), "{screen:?}");
+        assert!(screen[2].contains("Laundry") && screen[2].contains("2000-01-01"));
+        assert!(
+            screen[3].contains("kitchen") && screen[3].contains("Dishes"),
+            "{screen:#?}"
+        );
+        assert_eq!(terminal_color("#ff0000"), Some(Color::Indexed(196)));
+        assert_eq!(terminal_color("#90ee90"), Some(Color::Indexed(120)));
+        assert_eq!(terminal_color("red"), None);
+
+        // --- Act & Assert: The description is edited ---
+        press(&mut app, "eby hand\n", &conn).await;
+        let laundry = app.tasks[0].task.id.unwrap();
+        let task = Task::task_by_id(laundry, household_id, &conn)
+            .await
+            .unwrap();
+        assert_eq!(task.description, "by hand");
+
+        // --- Act & Assert: Tasks are filtered by label and back ---
+        press(&mut app, "l", &conn).await;
+        assert_eq!(app.label_filter, Some(label_id));
+        assert_eq!(app.tasks.len(), 1);
+        assert!(draw_tui(&app)[0].contains("labeled kitchen"));
+        press(&mut app, "l", &conn).await;
+        assert_eq!(app.tasks.len(), 2);
+
+        // --- Act & Assert: Done today moves the task down ---
+        press(&mut app, "d", &conn).await;
+        assert_eq!(Completion::for_task(laundry, &conn).await.len(), 1);
+        assert_eq!(app.tasks[1].task.id, Some(laundry));
+        assert_eq!(
+            app.tasks[1].task.updated_at.date(),
+            Local::now().date_naive()
+        );
+
+        // --- Act & Assert: Deleting asks first ---
+        press(&mut app, "jx", &conn).await;
+        assert_eq!(app.mode, Mode::ConfirmDelete);
+        assert!(draw_tui(&app)[9].contains("Move \"Laundry\" to the trash?"));
+        press(&mut app, "n", &conn).await;
+        assert_eq!(app.tasks.len(), 2);
+        press(&mut app, "xy", &conn).await;
+        assert_eq!(app.tasks.len(), 1);
+        assert_eq!(Task::trash(household_id, &conn).await[0].name, "Laundry");
+
+        press(&mut app, "q", &conn).await;
+        assert!(app.quit);
+    })
+}
+
+/// Returns the value of the metric line starting with `series`.
+fn metric(body: &str, series: &str) -> Option<f64> {
+    body.lines()
+        .find_map(|l| l.strip_prefix(series)?.strip_prefix(' '))
+        .and_then(|v| v.parse().ok())
+}
+
+#[test]
+fn metrics_count_tasks_and_requests() {
+    run_test!(|client, conn| {
+        // --- Arrange: An overdue labeled task, a task done today and requests ---
+        let household_id = household_id(&conn).await;
+        insert_label_by_post(&client, "bath", "#0000ff").await;
+        insert_label_by_post(&client, "garden", "#00ff00").await;
+        let labels = Label::all(household_id, &conn).await;
+        let bath = labels
+            .iter()
+            .find(|l| l.name == "bath")
+            .unwrap()
+            .id
+            .unwrap();
+        assert!(Task::insert_with_old_date("Bathtub", household_id, &conn).await);
+        let bathtub = Task::all(household_id, &conn).await[0].id.unwrap();
+        client
+            .post(format!("/{bathtub}"))
+            .header(ContentType::Form)
+            .body(format!(
+                "name=Bathtub&description=&updated_at=2000-01-01&interval_count=1&interval_unit=weeks&label_ids={bath}"
+            ))
+            .dispatch()
+            .await;
+        insert_task_by_post(&client, "Mop", "", "", &[]).await;
+        let mop = Task::all(household_id, &conn).await[1].id.unwrap();
+        client.post(format!("/{mop}/date")).dispatch().await;
+        for _ in 0..2 {
+            assert_eq!(client.get("/").dispatch().await.status(), Status::Ok);
+        }
+
+        // --- Act ---
+        let res = client.get("/metrics").dispatch().await;
+
+        // --- Assert ---
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.content_type().unwrap().sub(), "plain");
+        let body = res.into_string().await.unwrap();
+        let h = format!("household=\"{household_id}\"");
+        assert_eq!(metric(&body, &format!("last_date_tasks{{{h}}}")), Some(2.0));
+        assert_eq!(
+            metric(
+                &body,
+                &format!("last_date_overdue_tasks{{{h},label=\"bath\"}}")
+            ),
+            Some(1.0),
+            "{body}"
+        );
+        assert_eq!(
+            metric(
+                &body,
+                &format!("last_date_overdue_tasks{{{h},label=\"garden\"}}")
+            ),
+            Some(0.0)
+        );
+        assert_eq!(
+            metric(&body, &format!("last_date_completions{{{h}}}")),
+            Some(2.0)
+        );
+        assert!(body.contains("# TYPE last_date_completions gauge"));
+        assert_eq!(
+            metric(&body, &format!("last_date_completions_total{{{h}}}")),
+            Some(1.0)
+        );
+        assert!(body.contains("# TYPE last_date_completions_total counter"));
+        let age = metric(&body, &format!("last_date_oldest_task_age_days{{{h}}}")).unwrap();
+        assert!(age > 365.0 * 20.0, "{age}");
+        let index = "method=\"GET\",route=\"/?<query..>\"";
+        assert_eq!(
+            metric(
+                &body,
+                &format!("last_date_http_requests_total{{{index},status=\"200\"}}")
+            ),
+            Some(2.0)
+        );
+        assert_eq!(
+            metric(
+                &body,
+                &format!("last_date_http_request_duration_seconds_count{{{index}}}")
+            ),
+            Some(2.0)
+        );
+
+        // --- Act & Assert: Without a token, signing in is required ---
+        client.post("/logout").dispatch().await;
+        let res = client.get("/metrics").dispatch().await;
+        assert_eq!(res.status(), Status::Unauthorized);
+
+        // --- Act & Assert: A configured token is required ---
+        let figment = client
+            .rocket()
+            .figment()
+            .clone()
+            .merge(("metrics.token", "s3cret"));
+        let client = Client::tracked(super::rocket().configure(figment))
+            .await
+            .unwrap();
+        let res = client.get("/metrics").dispatch().await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        let res = client
+            .get("/metrics")
+            .header(Header::new("Authorization", "Bearer s3cre"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        log_in_by_post(&client, TEST_USER, TEST_PASSWORD).await;
+        let res = client.get("/metrics").dispatch().await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        let res = client
+            .get("/metrics")
+            .header(Header::new("Authorization", "Bearer s3cret"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+    })
+}
+
+#[test]
+fn metrics_for_users_leave_out_other_households() {
+    run_test!(|client, conn| {
+        // --- Arrange: A label of the test user, and another user ---
+        let home_id = household_id(&conn).await;
+        insert_label_by_post(&client, "secretlabel", "#0000ff").await;
+        let credentials = Credentials {
+            name: "other".to_string(),
+            password: TEST_PASSWORD.to_string(),
+        };
+        assert!(User::insert(credentials, &conn).await.is_some());
+
+        // --- Act: The other user reads the metrics ---
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, "other", TEST_PASSWORD).await;
+        let res = client.get("/metrics").dispatch().await;
+
+        // --- Assert: Only their own household is there ---
+        assert_eq!(res.status(), Status::Ok);
+        let body = res.into_string().await.unwrap();
+        assert!(!body.contains("secretlabel"), "{body}");
+        assert!(
+            !body.contains(&format!("household=\"{home_id}\"")),
+            "{body}"
+        );
+        assert!(body.contains("last_date_tasks{household="), "{body}");
+        assert!(body.contains("last_date_http_requests_total"), "{body}");
+
+        // --- Act & Assert: The test user still sees the label ---
+        client.post("/logout").dispatch().await;
+        log_in_by_post(&client, TEST_USER, TEST_PASSWORD).await;
+        let body = client
+            .get("/metrics")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        assert!(body.contains("label=\"secretlabel\""), "{body}");
+    })
+}
+
+#[test]
+fn activity_logs_changes_with_actor() {
+    run_test!(|client, conn| {
+        // --- Arrange: Changes on the web, then one by the CLI ---
+        let household_id = household_id(&conn).await;
+        insert_task_by_post(&client, "Dishes", "", "", &[]).await;
+        insert_label_by_post(&client, "kitchen", "#ff0000").await;
+        let task_id = Task::all_by_id(&conn).await[0].id.unwrap();
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+        update_label_by_post(&client, label_id, "kitchen", "#00ff00").await;
+        update_task_by_post(&client, task_id, "Dishes", "", "2001-02-03", &[label_id]).await;
+        run_cli(&conn, &["done", "Dishes"]).await.unwrap();
+
+        // --- Act ---
+        let all = Activity::list(household_id, ActivityQuery::default(), &conn).await;
+
+        // --- Assert: The latest first, with who made them ---
+        let events: Vec<&str> = all.iter().map(|a| a.event.as_str()).collect();
+        assert_eq!(
+            events,
+            vec![
+                "task.completed",
+                "task.updated",
+                "label.updated",
+                "label.created",
+                "task.created"
+            ]
+        );
+        let actors: Vec<Option<&str>> = all.iter().map(|a| a.actor.as_deref()).collect();
+        assert_eq!(actors[0], None);
+        assert!(actors[1..].iter().all(|a| *a == Some(TEST_USER)));
+        assert_eq!(
+            all[2].changes,
+            vec![Change {
+                field: "color".to_string(),
+                before: Some("#ff0000".to_string()),
+                after: Some("#00ff00".to_string()),
+            }]
+        );
+        let fields: Vec<(&str, Option<&str>)> = all[1]
+            .changes
+            .iter()
+            .map(|c| (c.field.as_str(), c.after.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("labels", Some("kitchen")),
+                ("updated_at", Some("2001-02-03 00:00"))
+            ]
+        );
+        assert!(all[4].changes.iter().all(|c| c.before.is_none()));
+
+        // --- Act & Assert: Filtering ---
+        let query = ActivityQuery {
+            entity: Some(Entity::Label),
+            ..ActivityQuery::default()
+        };
+        assert_eq!(Activity::list(household_id, query, &conn).await.len(), 2);
+        let actors = Activity::actors(household_id, &conn).await;
+        assert_eq!(actors.len(), 1);
+        assert_eq!(actors[0].1, TEST_USER);
+        let query = ActivityQuery {
+            entity: Some(Entity::Task),
+            id: Some(task_id),
+            user: Some(actors[0].0),
+            ..ActivityQuery::default()
+        };
+        assert_eq!(Activity::list(household_id, query, &conn).await.len(), 2);
+
+        // --- Act & Assert: The page ---
+        let res = client.get("/activity?event=label.updated").dispatch().await;
+        assert_eq!(res.status(), Status::Ok);
+        let html = Html::parse_document(&res.into_string().await.unwrap());
+        let rows = Selector::parse("tbody tr").unwrap();
+        let rows: Vec<String> = html
+            .select(&rows)
+            .map(|r| r.text().collect::<String>())
+            .collect();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].contains("#ff0000"), "{}", rows[0]);
+        assert!(rows[0].contains(TEST_USER));
+    })
+}
+
+#[test]
+fn activity_logs_undo_archive_trash_and_restores() {
+    run_test!(|client, conn| {
+        // --- Arrange: A task done long ago and a label ---
+        let household_id = household_id(&conn).await;
+        assert!(Task::insert_with_old_date("fan", household_id, &conn).await);
+        let id = Task::all_by_id(&conn).await[0].id.unwrap();
+        insert_label_by_post(&client, "summer", "#ffff00").await;
+        let label_id = Label::all(household_id, &conn).await[0].id.unwrap();
+
+        // --- Act: Undo "I did it today!", archive, undo a label deletion,
+        // restore the task from the trash, then purge it ---
+        client.post(format!("/{id}/date")).dispatch().await;
+        let path = undo_path(&client, "/").await.expect("undo button");
+        client.post(path).dispatch().await;
+        client.post(format!("/{id}/archive")).dispatch().await;
+        client.delete(format!("/label/{label_id}")).dispatch().await;
+        let path = undo_path(&client, "/label").await.expect("undo button");
+        client.post(path).dispatch().await;
+        client.delete(format!("/{id}")).dispatch().await;
+        client.post(format!("/trash/{id}/restore")).dispatch().await;
+        client.delete(format!("/{id}")).dispatch().await;
+        client.delete(format!("/trash/{id}")).dispatch().await;
+
+        // --- Assert: Each of them is logged by the user, after the label ---
+        let all = Activity::list(household_id, ActivityQuery::default(), &conn).await;
+        let events: Vec<&str> = all.iter().map(|a| a.event.as_str()).collect();
+        assert_eq!(
+            events[..events.len() - 1],
+            [
+                "task.purged",
+                "task.deleted",
+                "task.created",
+                "task.deleted",
+                "label.created",
+                "label.deleted",
+                "task.updated",
+                "task.updated",
+                "task.completed",
+            ]
+        );
+        assert!(all.iter().all(|a| a.actor.as_deref() == Some(TEST_USER)));
+        let undone = &all[7].changes;
+        assert_eq!(undone.len(), 1);
+        assert_eq!(undone[0].field, "updated_at");
+        assert_eq!(undone[0].after.as_deref(), Some("2000-01-01 00:00"));
+        assert_eq!(all[6].changes[0].field, "archived");
+
+        // --- Act: Add a labeled task by the CLI, and restore a backup ---
+        run_cli(&conn, &["add", "Mop", "-l", "summer"])
+            .await
+            .unwrap();
+        let snapshot = client
+            .get("/backup.json")
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+        restore_by_post(&client, &snapshot, "merge").await;
+
+        // --- Assert: The new tasks are logged with their labels ---
+        let all = Activity::list(household_id, ActivityQuery::default(), &conn).await;
+        let created: Vec<(&str, Option<&str>)> = all[..2]
+            .iter()
+            .map(|a| {
+                let labels = a.changes.iter().find(|c| c.field == "labels");
+                (a.event.as_str(), labels.and_then(|c| c.after.as_deref()))
+            })
+            .collect();
+        assert_eq!(
+            created,
+            vec![
+                ("task.created", Some("summer")),
+                ("task.created", Some("summer"))
+            ]
+        );
+        assert_eq!(all[0].actor.as_deref(), Some(TEST_USER));
+        assert_eq!(all[1].actor, None);
+    })
+}
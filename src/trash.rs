@@ -0,0 +1,80 @@
+//! Purging tasks which have been in the trash for long.
+
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime};
+use rocket::{Orbit, Rocket, tokio};
+use rocket_sync_db_pools::ConnectionPool;
+
+use crate::models::task::Task;
+use crate::{DbConn, DbConnection};
+
+/// `[default.trash]` table of `Rocket.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Tasks in the trash for this many days are purged. 0 keeps them
+    /// until they are purged by hand.
+    pub purge_after_days: u32,
+    /// 0 is taken as 1, since the check can't run all the time.
+    pub check_interval_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            purge_after_days: 30,
+            check_interval_secs: 60 * 60,
+        }
+    }
+}
+
+impl Config {
+    /// Time between purges. Never zero.
+    pub(crate) fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs.max(1))
+    }
+}
+
+/// Starts purging the trash periodically.
+pub fn start(rocket: &Rocket<Orbit>) {
+    let config = match rocket.figment().extract_inner::<Config>("trash") {
+        Ok(config) => config,
+        Err(e) if e.missing() => Config::default(),
+        Err(e) => {
+            log::error!("invalid trash config: {e}");
+            return;
+        }
+    };
+    if config.purge_after_days == 0 {
+        return;
+    }
+    let Some(pool) = DbConn::pool(rocket).cloned() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.check_interval());
+        loop {
+            interval.tick().await;
+            let deleted_before =
+                Local::now().naive_local() - chrono::Duration::days(config.purge_after_days.into());
+            match purge(&pool, deleted_before).await {
+                Ok(0) => {}
+                Ok(n) => log::info!("purged {n} task(s) from the trash"),
+                Err(e) => log::error!("failed to purge the trash: {e}"),
+            }
+        }
+    });
+}
+
+/// Purges tasks moved to the trash before `deleted_before`. Returns the
+/// number of tasks purged.
+pub async fn purge(
+    pool: &ConnectionPool<DbConn, DbConnection>,
+    deleted_before: NaiveDateTime,
+) -> Result<usize, String> {
+    let conn = pool.get().await.ok_or("no database connection")?;
+    conn.run(move |c| Task::purge_deleted_before(deleted_before, c))
+        .await
+        .map_err(|e| e.to_string())
+}
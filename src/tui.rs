@@ -0,0 +1,326 @@
+//! Full-screen terminal UI of `last-date-cli tui`, showing the table of the
+//! index page.
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Cell, Paragraph, Row, Table, TableState};
+
+use crate::DbConn;
+use crate::models::household::Household;
+use crate::models::label::Label;
+use crate::models::schedule::DueStatus;
+use crate::models::task::{Task, TaskQuery, TaskSummary, TaskUpdate};
+
+const HELP: &str =
+    "j/k: move  d: I did it today!  e: edit description  l: filter by label  x: delete  q: quit";
+
+/// What keys do at the moment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    /// Editing the description of the selected task.
+    Editing(String),
+    /// Asking whether to delete the selected task.
+    ConfirmDelete,
+}
+
+pub struct App {
+    household: Household,
+    pub tasks: Vec<TaskSummary>,
+    pub labels: Vec<Label>,
+    /// Only tasks with this label are shown.
+    pub label_filter: Option<i32>,
+    pub selected: usize,
+    pub mode: Mode,
+    /// Result of the last action, shown instead of the help.
+    pub message: Option<String>,
+    pub quit: bool,
+}
+
+/// Returns the xterm 256 color nearest to a color like `#90ee90`.
+pub fn terminal_color(hex: &str) -> Option<Color> {
+    let [r, g, b] = rgb(hex)?;
+    // Levels of each channel in the 6x6x6 color cube.
+    let level = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+    Some(Color::Indexed(16 + 36 * level(r) + 6 * level(g) + level(b)))
+}
+
+fn rgb(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Style of a label, with text readable on its color.
+fn label_style(hex: &str) -> Style {
+    let Some(bg) = terminal_color(hex) else {
+        return Style::default();
+    };
+    let [r, g, b] = rgb(hex).unwrap_or_default();
+    let luma = 299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b);
+    let fg = if luma > 128_000 {
+        Color::Black
+    } else {
+        Color::White
+    };
+    Style::default().bg(bg).fg(fg)
+}
+
+impl App {
+    pub async fn new(household: Household, conn: &DbConn) -> App {
+        let mut app = App {
+            household,
+            tasks: Vec::new(),
+            labels: Vec::new(),
+            label_filter: None,
+            selected: 0,
+            mode: Mode::Normal,
+            message: None,
+            quit: false,
+        };
+        app.reload(conn).await;
+        app
+    }
+
+    /// Reads tasks and labels again, keeping the selection in range.
+    pub async fn reload(&mut self, conn: &DbConn) {
+        let query = TaskQuery {
+            label: self.label_filter,
+            ..TaskQuery::default()
+        };
+        self.tasks = Task::list(self.household.id, query, conn).await;
+        self.labels = Label::all(self.household.id, conn).await;
+        self.selected = self.selected.min(self.tasks.len().saturating_sub(1));
+    }
+
+    pub fn selected_task(&self) -> Option<&TaskSummary> {
+        self.tasks.get(self.selected)
+    }
+
+    pub async fn handle_key(&mut self, key: KeyEvent, conn: &DbConn) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal(key.code, conn).await,
+            Mode::Editing(mut description) => match key.code {
+                KeyCode::Enter => self.save_description(description, conn).await,
+                KeyCode::Esc => self.message = Some("Canceled.".to_string()),
+                KeyCode::Backspace => {
+                    description.pop();
+                    self.mode = Mode::Editing(description);
+                }
+                KeyCode::Char(c) => {
+                    description.push(c);
+                    self.mode = Mode::Editing(description);
+                }
+                _ => self.mode = Mode::Editing(description),
+            },
+            Mode::ConfirmDelete => match key.code {
+                KeyCode::Char('y') => self.delete(conn).await,
+                _ => self.message = Some("Canceled.".to_string()),
+            },
+        }
+    }
+
+    async fn handle_normal(&mut self, code: KeyCode, conn: &DbConn) {
+        self.message = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down if self.selected + 1 < self.tasks.len() => {
+                self.selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('d') | KeyCode::Enter => self.done(conn).await,
+            KeyCode::Char('e') => {
+                if let Some(t) = self.selected_task() {
+                    self.mode = Mode::Editing(t.task.description.clone());
+                }
+            }
+            KeyCode::Char('l') => {
+                // Cycles through the labels, then back to all tasks.
+                let ids: Vec<i32> = self.labels.iter().filter_map(|l| l.id).collect();
+                self.label_filter = match self.label_filter {
+                    None => ids.first().copied(),
+                    Some(id) => ids.iter().skip_while(|&&i| i != id).nth(1).copied(),
+                };
+                self.selected = 0;
+                self.reload(conn).await;
+            }
+            KeyCode::Char('x') | KeyCode::Delete if self.selected_task().is_some() => {
+                self.mode = Mode::ConfirmDelete;
+            }
+            KeyCode::Char('r') => self.reload(conn).await,
+            _ => {}
+        }
+    }
+
+    async fn done(&mut self, conn: &DbConn) {
+        let Some((id, name)) = self
+            .selected_task()
+            .and_then(|t| Some((t.task.id?, t.task.name.clone())))
+        else {
+            return;
+        };
+        self.message = Some(
+            if Task::update_to_today(id, self.household.id, None, None, conn).await {
+                format!("Updated \"{name}\" to today.")
+            } else {
+                "The server failed.".to_string()
+            },
+        );
+        self.reload(conn).await;
+    }
+
+    async fn save_description(&mut self, description: String, conn: &DbConn) {
+        let Some(t) = self.selected_task() else {
+            return;
+        };
+        let Some(id) = t.task.id else {
+            return;
+        };
+        let update = TaskUpdate {
+            name: t.task.name.clone(),
+            description,
+            updated_at: String::new(),
+            label_ids: t.label_ids.clone(),
+            note: None,
+            correct_latest: false,
+            interval_count: t.task.interval_count,
+            interval_unit: t.task.interval_unit,
+        };
+        self.message = Some(
+            if Task::update(id, self.household.id, update, None, conn).await {
+                "Saved the description.".to_string()
+            } else {
+                "The server failed.".to_string()
+            },
+        );
+        self.reload(conn).await;
+    }
+
+    async fn delete(&mut self, conn: &DbConn) {
+        let Some((id, name)) = self
+            .selected_task()
+            .and_then(|t| Some((t.task.id?, t.task.name.clone())))
+        else {
+            return;
+        };
+        self.message = Some(
+            if Task::delete_with_id(id, self.household.id, None, conn).await {
+                format!("Moved \"{name}\" to the trash.")
+            } else {
+                "The server failed.".to_string()
+            },
+        );
+        self.reload(conn).await;
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [title, table, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let mut heading = format!("Tasks of {}", self.household.name);
+        if let Some(label) = self.labels.iter().find(|l| l.id == self.label_filter) {
+            heading.push_str(&format!(" labeled {}", label.name));
+        }
+        frame.render_widget(
+            Paragraph::new(heading).style(Style::default().add_modifier(Modifier::BOLD)),
+            title,
+        );
+
+        let rows: Vec<Row> = self.tasks.iter().map(|t| self.row(t)).collect();
+        let widths = [
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Length(12),
+            Constraint::Length(20),
+        ];
+        let header = Row::new(["Label", "Name", "Last updated", "Next due"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(
+            Table::new(rows, widths)
+                .header(header)
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            table,
+            &mut state,
+        );
+
+        let name = self.selected_task().map_or("", |t| t.task.name.as_str());
+        let status = match &self.mode {
+            Mode::Editing(description) => {
+                format!("Description of {name}: {description}_  (enter: save, esc: cancel)")
+            }
+            Mode::ConfirmDelete => format!("Move \"{name}\" to the trash? (y/n)"),
+            Mode::Normal => self.message.clone().unwrap_or_else(|| HELP.to_string()),
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+
+    fn row(&self, t: &TaskSummary) -> Row<'_> {
+        let labels: Vec<Span> = self
+            .labels
+            .iter()
+            .filter(|l| l.id.is_some_and(|id| t.label_ids.contains(&id)))
+            .flat_map(|l| {
+                [
+                    Span::styled(format!(" {} ", l.name), label_style(&l.color_hex)),
+                    Span::raw(" "),
+                ]
+            })
+            .collect();
+        let labels = if labels.is_empty() {
+            Line::from("-")
+        } else {
+            Line::from(labels)
+        };
+        let next_due = match (t.next_due, t.status) {
+            (Some(due), Some(DueStatus::Overdue)) => {
+                Cell::from(format!("{due} (overdue)")).style(Style::default().fg(Color::Red))
+            }
+            (Some(due), Some(DueStatus::DueSoon)) => {
+                Cell::from(due.to_string()).style(Style::default().fg(Color::Yellow))
+            }
+            (Some(due), _) => Cell::from(due.to_string()),
+            (None, _) => Cell::from("-"),
+        };
+        Row::new([
+            Cell::from(labels),
+            Cell::from(t.task.name.clone()),
+            Cell::from(t.task.updated_at.format("%Y-%m-%d").to_string()),
+            next_due,
+        ])
+    }
+}
+
+/// Runs the UI until `q` is pressed.
+pub async fn run(household: Household, conn: &DbConn) -> Result<(), String> {
+    let mut app = App::new(household, conn).await;
+    let mut terminal = ratatui::try_init().map_err(|e| e.to_string())?;
+    let result = async {
+        while !app.quit {
+            terminal
+                .draw(|frame| app.draw(frame))
+                .map_err(|e| e.to_string())?;
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                app.handle_key(key, conn).await;
+            }
+        }
+        Ok(())
+    }
+    .await;
+    ratatui::restore();
+    result
+}
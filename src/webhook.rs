@@ -0,0 +1,165 @@
+//! Sending task and label events to webhooks in the background.
+//!
+//! Events are queued in the database with the changes which cause them, see
+//! `change::record`, and sent from there, so that retries survive restarts.
+
+use std::time::Duration;
+
+use chrono::Local;
+use diesel::Connection;
+use hmac::{Hmac, Mac};
+use rocket::{Orbit, Rocket, tokio};
+use rocket_sync_db_pools::ConnectionPool;
+use sha2::Sha256;
+
+use crate::models::webhook::{Delivery, Job, Webhook};
+use crate::{DbConn, DbConnection};
+
+/// Header with the hex HMAC-SHA256 of the body, keyed by the webhook secret.
+pub const SIGNATURE_HEADER: &str = "X-Last-Date-Signature";
+/// Header with the event name, like `task.completed`.
+pub const EVENT_HEADER: &str = "X-Last-Date-Event";
+
+/// `[default.webhooks]` table of `Rocket.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Attempts per event, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry. It doubles for each following one.
+    pub retry_delay_ms: u64,
+    pub timeout_ms: u64,
+    /// How often the queue is checked for events to send. 0 is taken as 1.
+    pub poll_interval_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_attempts: 5,
+            retry_delay_ms: 1000,
+            timeout_ms: 10_000,
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+impl Config {
+    /// Time between checks of the queue. Never zero.
+    pub(crate) fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.max(1))
+    }
+}
+
+/// Managed state shared by deliveries.
+pub struct Webhooks {
+    client: reqwest::Client,
+    config: Config,
+}
+
+impl Webhooks {
+    pub fn new(config: Config) -> Webhooks {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .expect("HTTP client");
+        Webhooks { client, config }
+    }
+}
+
+/// Returns the value of `SIGNATURE_HEADER` for the body.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes any key");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Starts sending queued events to webhooks.
+///
+/// Each event is retried with backoff until the webhook answers with 2xx or
+/// `max_attempts` are made, and each attempt is logged.
+pub fn start(rocket: &Rocket<Orbit>) {
+    let Some(webhooks) = rocket.state::<Webhooks>() else {
+        return;
+    };
+    let Some(pool) = DbConn::pool(rocket).cloned() else {
+        return;
+    };
+    let client = webhooks.client.clone();
+    let config = webhooks.config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval());
+        loop {
+            interval.tick().await;
+            if let Err(e) = send_due(&pool, &client, &config).await {
+                log::error!("failed to send webhooks: {e}");
+            }
+        }
+    });
+}
+
+/// Starts an attempt of each event due now.
+async fn send_due(
+    pool: &ConnectionPool<DbConn, DbConnection>,
+    client: &reqwest::Client,
+    config: &Config,
+) -> Result<(), String> {
+    let conn = pool.get().await.ok_or("no database connection")?;
+    let now = Local::now().naive_local();
+    // Long enough for the attempt to time out and be logged.
+    let claimed_until = now + chrono::Duration::milliseconds(2 * config.timeout_ms as i64);
+    let jobs = conn
+        .run(move |c| Job::claim_due(now, claimed_until, c))
+        .await
+        .map_err(|e| e.to_string())?;
+    for (job, webhook) in jobs {
+        tokio::spawn(attempt(
+            job,
+            webhook,
+            pool.clone(),
+            client.clone(),
+            config.clone(),
+        ));
+    }
+    Ok(())
+}
+
+async fn attempt(
+    job: Job,
+    webhook: Webhook,
+    pool: ConnectionPool<DbConn, DbConnection>,
+    client: reqwest::Client,
+    config: Config,
+) {
+    let result = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &job.event)
+        .header(SIGNATURE_HEADER, sign(&webhook.secret, &job.body))
+        .body(job.body)
+        .send()
+        .await
+        .map(|res| res.status().as_u16())
+        .map_err(|e| e.to_string());
+    let attempted_at = Local::now().naive_local();
+    let attempt = job.attempts + 1;
+    let delivered = result.as_ref().is_ok_and(|s| (200..300).contains(s));
+    let retry_at = (!delivered && attempt < i32::try_from(config.max_attempts).unwrap_or(i32::MAX))
+        .then(|| {
+            let delay = config.retry_delay_ms << (attempt - 1).min(20);
+            attempted_at + chrono::Duration::milliseconds(delay as i64)
+        });
+    let Some(conn) = pool.get().await else {
+        return;
+    };
+    conn.run(move |c| {
+        let logged = c.transaction(|c| {
+            Delivery::record(webhook.id, &job.event, attempt, &result, attempted_at, c)?;
+            Job::attempted(job.id, retry_at, c)
+        });
+        if let Err(e) = logged {
+            log::error!("database error: {e}");
+        }
+    })
+    .await;
+}